@@ -0,0 +1,5 @@
+-- Migration to add DJ attributes (rating, colour label, comments, play count) to songs table
+ALTER TABLE songs ADD COLUMN rating INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE songs ADD COLUMN colour TEXT;
+ALTER TABLE songs ADD COLUMN comments TEXT;
+ALTER TABLE songs ADD COLUMN play_count INTEGER NOT NULL DEFAULT 0;
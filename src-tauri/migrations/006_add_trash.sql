@@ -0,0 +1,2 @@
+-- Migration to soft-delete songs into the trash bin (unix timestamp, NULL when not trashed)
+ALTER TABLE songs ADD COLUMN deleted_at INTEGER;
@@ -0,0 +1,15 @@
+-- Migration to record every library mutation with before/after snapshots of the song
+CREATE TABLE IF NOT EXISTS audit_log (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    change_id INTEGER NOT NULL,
+    song_id TEXT NOT NULL,
+    action TEXT NOT NULL,
+    actor TEXT,
+    before TEXT,
+    after TEXT,
+    created_at INTEGER NOT NULL DEFAULT (CAST(strftime('%s', 'now') AS INTEGER)),
+    undone INTEGER NOT NULL DEFAULT 0
+);
+
+CREATE INDEX IF NOT EXISTS idx_audit_log_change_id ON audit_log (change_id);
+CREATE INDEX IF NOT EXISTS idx_audit_log_song_id ON audit_log (song_id);
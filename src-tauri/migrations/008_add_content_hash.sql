@@ -0,0 +1,3 @@
+-- Migration to track each song file's SHA-256 content hash and modification time
+ALTER TABLE songs ADD COLUMN content_hash TEXT;
+ALTER TABLE songs ADD COLUMN file_mtime INTEGER;
@@ -0,0 +1,2 @@
+-- Migration to store song duration in seconds, used to match relocated files
+ALTER TABLE songs ADD COLUMN duration REAL;
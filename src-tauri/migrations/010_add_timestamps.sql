@@ -0,0 +1,13 @@
+-- Migration to record when each song entered the library, last changed, and was downloaded
+ALTER TABLE songs ADD COLUMN created_at INTEGER;
+ALTER TABLE songs ADD COLUMN updated_at INTEGER;
+ALTER TABLE songs ADD COLUMN downloaded_at INTEGER;
+
+-- Back-fill from the file mtimes recorded by content hashing; songs that have
+-- not been hashed yet are filled in when the hash back-fill reaches them
+UPDATE songs SET
+    created_at = file_mtime,
+    updated_at = file_mtime,
+    downloaded_at = CASE WHEN source_url IS NOT NULL THEN file_mtime END
+WHERE
+    file_mtime IS NOT NULL;
@@ -0,0 +1,4 @@
+-- Migration to store a track's version, remixer and featured artists apart from its title
+ALTER TABLE songs ADD COLUMN version TEXT;
+ALTER TABLE songs ADD COLUMN remixer TEXT;
+ALTER TABLE songs ADD COLUMN featured_artists TEXT;
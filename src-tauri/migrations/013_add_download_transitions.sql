@@ -0,0 +1,2 @@
+-- Migration to keep the timestamped status history of each download
+ALTER TABLE downloads ADD COLUMN transitions TEXT;
@@ -0,0 +1,2 @@
+-- Migration to let queued downloads be reordered, higher priorities start first
+ALTER TABLE downloads ADD COLUMN priority INTEGER NOT NULL DEFAULT 0;
@@ -0,0 +1,2 @@
+-- Migration to remember the quality profile each download was queued with
+ALTER TABLE downloads ADD COLUMN profile TEXT;
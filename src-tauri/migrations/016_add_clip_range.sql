@@ -0,0 +1,3 @@
+-- Migration to keep the section of the source a song was cut from, in seconds
+ALTER TABLE songs ADD COLUMN clip_start REAL;
+ALTER TABLE songs ADD COLUMN clip_end REAL;
@@ -0,0 +1,2 @@
+-- Migration to number the tracks split out of an album or mix
+ALTER TABLE songs ADD COLUMN track_number INTEGER;
@@ -0,0 +1,2 @@
+-- Migration to remember which quality profile a song was downloaded with, so re-downloads match
+ALTER TABLE songs ADD COLUMN quality_profile TEXT;
@@ -0,0 +1,2 @@
+-- Migration to keep the retry history of a download across restarts
+ALTER TABLE downloads ADD COLUMN logs TEXT;
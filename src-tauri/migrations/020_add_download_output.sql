@@ -0,0 +1,2 @@
+-- Migration to remember the file name a download writes to, so its partial files can be found
+ALTER TABLE downloads ADD COLUMN output TEXT;
@@ -0,0 +1,2 @@
+INSERT INTO audit_log (change_id, song_id, action, actor, before, after)
+VALUES (?, ?, ?, ?, ?, ?);
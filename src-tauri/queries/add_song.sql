@@ -1,2 +1,28 @@
-INSERT OR REPLACE INTO songs (id, title, artist, album, filename, source_url, tags)
-VALUES (?, ?, ?, ?, ?, ?, ?);
+INSERT INTO songs (id, title, artist, album, filename, source_url, tags, rating, colour, comments, play_count, content_hash, file_mtime, duration, created_at, updated_at, downloaded_at, version, remixer, featured_artists, clip_start, clip_end, track_number, quality_profile)
+VALUES (
+    ?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14,
+    COALESCE(?15, CAST(strftime('%s', 'now') AS INTEGER)),
+    CAST(strftime('%s', 'now') AS INTEGER),
+    ?16, ?17, ?18, ?19, ?20, ?21, ?22, ?23
+)
+-- Re-adding an existing song (e.g. a re-download) only replaces what the download
+-- provides, keeping the user's tags, rating, colour, comments, plays and date added
+ON CONFLICT(id) DO UPDATE SET
+    title = excluded.title,
+    artist = excluded.artist,
+    album = excluded.album,
+    filename = excluded.filename,
+    source_url = excluded.source_url,
+    content_hash = excluded.content_hash,
+    file_mtime = excluded.file_mtime,
+    duration = excluded.duration,
+    updated_at = excluded.updated_at,
+    downloaded_at = excluded.downloaded_at,
+    version = excluded.version,
+    remixer = excluded.remixer,
+    featured_artists = excluded.featured_artists,
+    clip_start = excluded.clip_start,
+    clip_end = excluded.clip_end,
+    track_number = excluded.track_number,
+    quality_profile = excluded.quality_profile,
+    deleted_at = NULL;
@@ -0,0 +1,14 @@
+SELECT COUNT(*)
+FROM songs
+WHERE
+    deleted_at IS NULL
+    AND (
+        ?1 IS NULL
+        OR title LIKE ?1 ESCAPE '\'
+        OR artist LIKE ?1 ESCAPE '\'
+        OR album LIKE ?1 ESCAPE '\'
+    )
+    AND (?2 IS NULL OR (',' || REPLACE(tags, ' ', '') || ',') LIKE ?2 ESCAPE '\')
+    AND rating >= ?3
+    AND (?4 IS NULL OR colour = ?4)
+    AND (?5 IS NULL OR created_at >= ?5);
@@ -4,6 +4,21 @@ UPDATE songs SET
     album = ?3,
     filename = ?4,
     source_url = ?5,
-    tags = ?6
+    tags = ?6,
+    rating = ?7,
+    colour = ?8,
+    comments = ?9,
+    play_count = ?10,
+    content_hash = ?11,
+    file_mtime = ?12,
+    duration = ?13,
+    version = ?14,
+    remixer = ?15,
+    featured_artists = ?16,
+    clip_start = ?17,
+    clip_end = ?18,
+    track_number = ?19,
+    quality_profile = ?20,
+    updated_at = CAST(strftime('%s', 'now') AS INTEGER)
 WHERE 
-    id = ?7;
+    id = ?21;
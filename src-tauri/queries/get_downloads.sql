@@ -0,0 +1,3 @@
+SELECT *
+FROM downloads
+ORDER BY rowid;
@@ -0,0 +1,5 @@
+SELECT *
+FROM songs
+WHERE
+    deleted_at IS NOT NULL
+    AND deleted_at <= CAST(strftime('%s', 'now') AS INTEGER) - ?1;
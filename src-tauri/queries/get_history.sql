@@ -0,0 +1,7 @@
+SELECT *
+FROM audit_log
+WHERE
+    ?1 IS NULL
+    OR song_id = ?1
+ORDER BY id DESC
+LIMIT ?2;
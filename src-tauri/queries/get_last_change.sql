@@ -0,0 +1,9 @@
+SELECT *
+FROM audit_log
+WHERE
+    change_id = (
+        SELECT MAX(change_id)
+        FROM audit_log
+        WHERE undone = 0
+    )
+ORDER BY id DESC;
@@ -0,0 +1,4 @@
+SELECT * 
+FROM songs 
+WHERE 
+    filename = ?1
@@ -5,5 +5,25 @@ SELECT
     album,
     filename,
     source_url,
-    tags
-FROM songs;
+    tags,
+    rating,
+    colour,
+    comments,
+    play_count,
+    deleted_at,
+    content_hash,
+    file_mtime,
+    duration,
+    created_at,
+    updated_at,
+    downloaded_at,
+    version,
+    remixer,
+    featured_artists,
+    clip_start,
+    clip_end,
+    track_number,
+    quality_profile
+FROM songs
+WHERE
+    deleted_at IS NULL;
@@ -0,0 +1,41 @@
+SELECT
+    id,
+    title,
+    artist,
+    album,
+    filename,
+    source_url,
+    tags,
+    rating,
+    colour,
+    comments,
+    play_count,
+    deleted_at,
+    content_hash,
+    file_mtime,
+    duration,
+    created_at,
+    updated_at,
+    downloaded_at,
+    version,
+    remixer,
+    featured_artists,
+    clip_start,
+    clip_end,
+    track_number,
+    quality_profile
+FROM songs
+WHERE
+    deleted_at IS NULL
+    AND (
+        ?1 IS NULL
+        OR title LIKE ?1 ESCAPE '\'
+        OR artist LIKE ?1 ESCAPE '\'
+        OR album LIKE ?1 ESCAPE '\'
+    )
+    AND (?2 IS NULL OR (',' || REPLACE(tags, ' ', '') || ',') LIKE ?2 ESCAPE '\')
+    AND rating >= ?3
+    AND (?4 IS NULL OR colour = ?4)
+    AND (?5 IS NULL OR created_at >= ?5)
+ORDER BY {order_by}
+LIMIT ?6 OFFSET ?7;
@@ -0,0 +1,5 @@
+SELECT *
+FROM songs
+WHERE
+    deleted_at IS NOT NULL
+ORDER BY deleted_at DESC;
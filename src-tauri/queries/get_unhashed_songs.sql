@@ -0,0 +1,5 @@
+SELECT *
+FROM songs
+WHERE
+    content_hash IS NULL
+    AND deleted_at IS NULL;
@@ -0,0 +1,4 @@
+UPDATE audit_log SET
+    undone = 1
+WHERE 
+    change_id = ?1;
@@ -0,0 +1,3 @@
+DELETE FROM downloads
+WHERE
+    id = ?1;
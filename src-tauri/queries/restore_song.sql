@@ -0,0 +1,5 @@
+UPDATE songs SET
+    deleted_at = NULL,
+    updated_at = CAST(strftime('%s', 'now') AS INTEGER)
+WHERE 
+    id = ?1;
@@ -0,0 +1,2 @@
+INSERT OR REPLACE INTO songs (id, title, artist, album, filename, source_url, tags, rating, colour, comments, play_count, deleted_at, content_hash, file_mtime, duration, created_at, updated_at, downloaded_at, version, remixer, featured_artists, clip_start, clip_end, track_number, quality_profile)
+VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?);
@@ -0,0 +1,17 @@
+INSERT INTO downloads (id, url, title, status, progress, detailed_status, metadata, attempts, error, transitions, priority, profile, logs, output)
+VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14)
+ON CONFLICT(id) DO UPDATE SET
+    url = excluded.url,
+    title = excluded.title,
+    status = excluded.status,
+    progress = excluded.progress,
+    detailed_status = excluded.detailed_status,
+    metadata = excluded.metadata,
+    attempts = excluded.attempts,
+    error = excluded.error,
+    transitions = excluded.transitions,
+    priority = excluded.priority,
+    profile = excluded.profile,
+    logs = excluded.logs,
+    output = excluded.output,
+    updated_at = CAST(strftime('%s', 'now') AS INTEGER);
@@ -5,9 +5,30 @@ SELECT
     album, 
     filename,
     source_url,
-    tags
+    tags,
+    rating,
+    colour,
+    comments,
+    play_count,
+    deleted_at,
+    content_hash,
+    file_mtime,
+    duration,
+    created_at,
+    updated_at,
+    downloaded_at,
+    version,
+    remixer,
+    featured_artists,
+    clip_start,
+    clip_end,
+    track_number,
+    quality_profile
 FROM songs 
 WHERE 
-    title LIKE ?1 
-    OR artist LIKE ?1 
-    OR album LIKE ?1
+    deleted_at IS NULL
+    AND (
+        title LIKE ?1 ESCAPE '\'
+        OR artist LIKE ?1 ESCAPE '\'
+        OR album LIKE ?1 ESCAPE '\'
+    )
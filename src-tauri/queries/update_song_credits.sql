@@ -0,0 +1,7 @@
+UPDATE songs SET
+    version = ?1,
+    remixer = ?2,
+    featured_artists = ?3,
+    updated_at = CAST(strftime('%s', 'now') AS INTEGER)
+WHERE 
+    id = ?4;
@@ -0,0 +1,5 @@
+UPDATE songs SET
+    filename = ?1,
+    updated_at = CAST(strftime('%s', 'now') AS INTEGER)
+WHERE 
+    id = ?2;
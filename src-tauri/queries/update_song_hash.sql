@@ -0,0 +1,9 @@
+UPDATE songs SET
+    content_hash = ?1,
+    file_mtime = ?2,
+    -- Songs added before timestamps existed take theirs from the file
+    created_at = COALESCE(created_at, ?2),
+    updated_at = COALESCE(updated_at, ?2),
+    downloaded_at = COALESCE(downloaded_at, CASE WHEN source_url IS NOT NULL THEN ?2 END)
+WHERE 
+    id = ?3;
@@ -1,4 +1,5 @@
 UPDATE songs SET
-    tags = ?1
+    tags = ?1,
+    updated_at = CAST(strftime('%s', 'now') AS INTEGER)
 WHERE 
     id = ?2;
@@ -0,0 +1,292 @@
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, LazyLock, Mutex};
+use tauri::http::{Response, StatusCode};
+use tauri::AppHandle;
+use tokio::process::Command;
+
+use crate::bundler;
+use crate::db::Database;
+use crate::protocol::{self, not_found, percent_decode};
+
+/// Name of the URI scheme the webview loads artwork from, as
+/// `cue-art://localhost/<song_id>/<size>`.
+pub const PROTOCOL: &str = "cue-art";
+
+/// Edge lengths in pixels of the thumbnails generated for each song.
+pub const SIZES: [u32; 3] = [64, 256, 512];
+
+/// Left in a song's cache folder when its file has no cover, so it isn't probed again.
+const NO_ARTWORK: &str = "none";
+
+/// One lock per song being generated, so parallel requests wait for a single ffmpeg run.
+static GENERATING: LazyLock<Mutex<HashMap<String, Arc<tokio::sync::Mutex<()>>>>> =
+    LazyLock::new(Default::default);
+
+fn cache_dir(library_path: &str, song_id: &str) -> PathBuf {
+    // Song ids may contain characters that aren't valid in file names
+    let key = format!("{:x}", Sha256::digest(song_id.as_bytes()));
+    Path::new(library_path)
+        .join(".cache")
+        .join("artwork")
+        .join(key)
+}
+
+pub fn thumbnail_path(library_path: &str, song_id: &str, size: u32) -> PathBuf {
+    cache_dir(library_path, song_id).join(format!("{}.jpg", size))
+}
+
+/// Smallest generated size that is at least `requested`, so images are only scaled down.
+fn pick_size(requested: u32) -> u32 {
+    SIZES
+        .iter()
+        .copied()
+        .find(|s| *s >= requested)
+        .unwrap_or(SIZES[SIZES.len() - 1])
+}
+
+/// Extracts the embedded cover of a song and writes every thumbnail size.
+/// Returns false when the file has no artwork.
+pub async fn generate(app: &AppHandle, db: &Database, song_id: &str) -> anyhow::Result<bool> {
+    let tools = || Ok((bundler::ffmpeg_path(app)?, bundler::ffprobe_path(app)?));
+    generate_with(tools, db, song_id).await
+}
+
+/// `tools` returns the ffmpeg and ffprobe binaries to run.
+async fn generate_with(
+    tools: impl FnOnce() -> anyhow::Result<(PathBuf, PathBuf)>,
+    db: &Database,
+    song_id: &str,
+) -> anyhow::Result<bool> {
+    let lock = GENERATING
+        .lock()
+        .unwrap()
+        .entry(song_id.to_string())
+        .or_default()
+        .clone();
+    let result = {
+        let _generating = lock.lock().await;
+        extract(tools, db, song_id).await
+    };
+    drop(lock);
+    // Forget locks nobody else is waiting on
+    GENERATING
+        .lock()
+        .unwrap()
+        .retain(|_, lock| Arc::strong_count(lock) > 1);
+    result
+}
+
+async fn extract(
+    tools: impl FnOnce() -> anyhow::Result<(PathBuf, PathBuf)>,
+    db: &Database,
+    song_id: &str,
+) -> anyhow::Result<bool> {
+    // Another request may have finished while this one waited
+    let dir = cache_dir(&db.library_path, song_id);
+    if dir.join(NO_ARTWORK).exists() {
+        return Ok(false);
+    }
+    if SIZES
+        .iter()
+        .all(|&size| thumbnail_path(&db.library_path, song_id, size).exists())
+    {
+        return Ok(true);
+    }
+
+    let song = db
+        .get_song_by_id(song_id)
+        .await?
+        .ok_or_else(|| anyhow::anyhow!("Song {} not found", song_id))?;
+    let song_path = Path::new(&db.library_path)
+        .join("Songs")
+        .join(&song.filename);
+    if !song_path.exists() {
+        return Ok(false);
+    }
+
+    let (ffmpeg, ffprobe) = tools()?;
+    if !has_picture(&ffprobe, &song_path).await? {
+        std::fs::create_dir_all(&dir)?;
+        std::fs::write(dir.join(NO_ARTWORK), b"")?;
+        return Ok(false);
+    }
+    std::fs::create_dir_all(&dir)?;
+
+    for size in SIZES {
+        let mut cmd = Command::new(&ffmpeg);
+        cmd.args(["-y", "-v", "error", "-i"])
+            .arg(&song_path)
+            .args(["-an", "-map", "0:v:0", "-frames:v", "1", "-vf"])
+            .arg(format!(
+                "scale={0}:{0}:force_original_aspect_ratio=decrease",
+                size
+            ))
+            .arg(thumbnail_path(&db.library_path, song_id, size));
+
+        #[cfg(windows)]
+        cmd.creation_flags(bundler::CREATE_NO_WINDOW);
+
+        let output = cmd.output().await?;
+        if !output.status.success() {
+            // Nothing is cached, so the next request tries again
+            let _ = std::fs::remove_dir_all(&dir);
+            anyhow::bail!(
+                "ffmpeg failed: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            );
+        }
+    }
+    Ok(true)
+}
+
+/// Whether the file has a video stream, which is where covers are embedded.
+async fn has_picture(ffprobe: &Path, path: &Path) -> anyhow::Result<bool> {
+    let mut cmd = Command::new(ffprobe);
+    cmd.args(["-v", "error", "-select_streams", "v"])
+        .args(["-show_entries", "stream=index", "-of", "csv=p=0"])
+        .arg(path);
+
+    #[cfg(windows)]
+    cmd.creation_flags(bundler::CREATE_NO_WINDOW);
+
+    let output = cmd.output().await?;
+    if !output.status.success() {
+        anyhow::bail!(
+            "ffprobe failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(!output.stdout.trim_ascii().is_empty())
+}
+
+/// Generates thumbnails for songs that haven't been looked at yet.
+pub async fn backfill(app: &AppHandle, db: &Database) -> anyhow::Result<usize> {
+    let mut generated = 0;
+    for song in db.get_songs().await? {
+        if cache_dir(&db.library_path, &song.id).exists() {
+            continue;
+        }
+        match generate(app, db, &song.id).await {
+            Ok(true) => generated += 1,
+            Ok(false) => {}
+            // Left uncached so it's retried when the song is shown
+            Err(e) => eprintln!("Warning: Failed to generate artwork for {}: {}", song.id, e),
+        }
+    }
+    Ok(generated)
+}
+
+/// Drops the cached thumbnails of a song, or its record of having none.
+pub fn remove(library_path: &str, song_id: &str) {
+    let dir = cache_dir(library_path, song_id);
+    if dir.exists() {
+        if let Err(e) = std::fs::remove_dir_all(&dir) {
+            eprintln!("Failed to remove artwork {}: {}", dir.display(), e);
+        }
+    }
+}
+
+/// Serves `/<song_id>/<size>` from the thumbnail cache, generating the
+/// thumbnails on first request.
+pub async fn handle_request(app: &AppHandle, path: &str) -> Response<Vec<u8>> {
+    let mut parts = path.trim_start_matches('/').rsplitn(2, '/');
+    let (size, song_id) = match (parts.next(), parts.next()) {
+        (Some(size), Some(id)) => (size.parse::<u32>().unwrap_or(SIZES[1]), percent_decode(id)),
+        _ => return not_found(),
+    };
+
+    let db = match protocol::database(app) {
+        Some(db) => db,
+        None => return not_found(),
+    };
+
+    let file = thumbnail_path(&db.library_path, &song_id, pick_size(size));
+    if !file.exists() {
+        match generate(app, &db, &song_id).await {
+            Ok(true) => {}
+            Ok(false) => return not_found(),
+            Err(e) => {
+                eprintln!("Failed to generate artwork for {}: {}", song_id, e);
+                return not_found();
+            }
+        }
+    }
+
+    match tokio::fs::read(&file).await {
+        Ok(bytes) => protocol::response(StatusCode::OK, bytes, "image/jpeg"),
+        Err(_) => not_found(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::entities::Song;
+
+    async fn library_with_song(dir: &Path) -> Database {
+        std::fs::create_dir_all(dir.join("Songs")).unwrap();
+        std::fs::write(dir.join("Songs").join("song.mp3"), b"audio").unwrap();
+        let db = Database::open(&dir.to_string_lossy()).await.unwrap();
+        let song = Song {
+            id: "1".to_string(),
+            title: "Song".to_string(),
+            artist: "Artist".to_string(),
+            filename: "song.mp3".to_string(),
+            ..Default::default()
+        };
+        db.add_song(&song).await.unwrap();
+        db
+    }
+
+    #[test]
+    fn test_pick_size() {
+        assert_eq!(pick_size(10), 64);
+        assert_eq!(pick_size(64), 64);
+        assert_eq!(pick_size(200), 256);
+        assert_eq!(pick_size(2000), 512);
+    }
+
+    // `true` stands in for an ffprobe that lists no video stream
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_no_artwork_is_remembered() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = library_with_song(dir.path()).await;
+        let no_cover = || Ok((PathBuf::from("false"), PathBuf::from("true")));
+
+        // Parallel requests for the same song share one run
+        let (a, b) = tokio::join!(
+            generate_with(no_cover, &db, "1"),
+            generate_with(no_cover, &db, "1")
+        );
+        assert!(!a.unwrap() && !b.unwrap());
+        assert!(cache_dir(&db.library_path, "1").join(NO_ARTWORK).exists());
+
+        // Later requests don't start ffmpeg again
+        let unused = || Err(anyhow::anyhow!("ffmpeg was started"));
+        assert!(!generate_with(unused, &db, "1").await.unwrap());
+
+        // A re-download clears the marker, so the new file gets looked at
+        remove(&db.library_path, "1");
+        assert!(generate_with(unused, &db, "1").await.is_err());
+        assert!(GENERATING.lock().unwrap().is_empty());
+    }
+
+    // `echo` lists a stream, `false` is an ffmpeg that fails to extract it
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_failed_extraction_is_not_remembered() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = library_with_song(dir.path()).await;
+        let failing = || Ok((PathBuf::from("false"), PathBuf::from("echo")));
+
+        assert!(generate_with(failing, &db, "1").await.is_err());
+        assert!(!cache_dir(&db.library_path, "1").exists());
+
+        // The next request runs the tools again
+        let unused = || Err(anyhow::anyhow!("ffmpeg was started"));
+        assert!(generate_with(unused, &db, "1").await.is_err());
+    }
+}
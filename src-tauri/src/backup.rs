@@ -0,0 +1,428 @@
+use serde::Serialize;
+use sqlx::sqlite::{SqliteConnectOptions, SqliteJournalMode};
+use sqlx::{ConnectOptions, Connection};
+use std::fs::{self, File};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tauri::{AppHandle, Manager};
+use zip::write::SimpleFileOptions;
+
+use crate::config::{Config, ConfigState};
+use crate::db::{self, Database, DbState};
+
+const BACKUP_DIR: &str = "Backups";
+const BACKUP_PREFIX: &str = "cue-backup-";
+const DB_ENTRY: &str = "songs.db";
+const CONFIG_ENTRY: &str = "config.yaml";
+const REKORDBOX_ENTRY: &str = "rekordbox.xml";
+
+/// How often the scheduler wakes up to check whether a snapshot is due.
+const SCHEDULER_TICK: Duration = Duration::from_secs(10 * 60);
+
+#[derive(Clone, Serialize)]
+pub struct BackupInfo {
+    pub path: String,
+    pub created_at: i64,
+    pub size: u64,
+}
+
+fn backup_dir(library_path: &str) -> PathBuf {
+    Path::new(library_path).join(BACKUP_DIR)
+}
+
+fn unix_now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or_default()
+}
+
+fn add_file(zip: &mut zip::ZipWriter<File>, name: &str, path: &Path) -> anyhow::Result<()> {
+    zip.start_file(name, SimpleFileOptions::default())?;
+    std::io::copy(&mut File::open(path)?, zip)?;
+    Ok(())
+}
+
+/// Creates `cue-backup-<created_at>.zip`, or `cue-backup-<created_at>-<n>.zip`
+/// when snapshots were already taken within the same second.
+fn create_archive(dir: &Path, created_at: i64) -> std::io::Result<(PathBuf, File)> {
+    let mut counter = 0;
+    loop {
+        let name = match counter {
+            0 => format!("{}{}.zip", BACKUP_PREFIX, created_at),
+            n => format!("{}{}-{}.zip", BACKUP_PREFIX, created_at, n),
+        };
+        let path = dir.join(name);
+        match File::options().write(true).create_new(true).open(&path) {
+            Ok(file) => return Ok((path, file)),
+            Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => counter += 1,
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+/// Creation time and same-second counter of a snapshot, from its file stem.
+fn parse_stem(stem: &str) -> Option<(i64, u32)> {
+    let rest = stem.strip_prefix(BACKUP_PREFIX)?;
+    match rest.split_once('-') {
+        Some((created_at, counter)) => Some((created_at.parse().ok()?, counter.parse().ok()?)),
+        None => Some((rest.parse().ok()?, 0)),
+    }
+}
+
+/// Writes a consistent snapshot of the library database, zipped with the config
+/// and the Rekordbox export, into `<library>/Backups`.
+pub async fn create_snapshot(db: &Database) -> anyhow::Result<BackupInfo> {
+    let dir = backup_dir(&db.library_path);
+    fs::create_dir_all(&dir)?;
+
+    // VACUUM INTO gives a transactionally consistent copy while the pool stays open
+    let staging = tempfile::tempdir()?;
+    let db_copy = staging.path().join(DB_ENTRY);
+    sqlx::query("VACUUM INTO ?1")
+        .bind(db_copy.to_string_lossy().to_string())
+        .execute(&db.pool)
+        .await?;
+
+    let created_at = unix_now();
+    let (archive_path, file) = create_archive(&dir, created_at)?;
+    let mut zip = zip::ZipWriter::new(file);
+
+    add_file(&mut zip, DB_ENTRY, &db_copy)?;
+
+    if let Ok(config_path) = crate::config::config_path() {
+        if config_path.exists() {
+            add_file(&mut zip, CONFIG_ENTRY, &config_path)?;
+        }
+    }
+
+    let xml_path = Path::new(&db.library_path).join(REKORDBOX_ENTRY);
+    if xml_path.exists() {
+        add_file(&mut zip, REKORDBOX_ENTRY, &xml_path)?;
+    }
+
+    zip.finish()?;
+
+    Ok(BackupInfo {
+        path: archive_path.to_string_lossy().to_string(),
+        created_at,
+        size: fs::metadata(&archive_path)?.len(),
+    })
+}
+
+/// Lists the snapshots in the library, newest first.
+pub fn list_snapshots(library_path: &str) -> anyhow::Result<Vec<BackupInfo>> {
+    let dir = backup_dir(library_path);
+    if !dir.exists() {
+        return Ok(Vec::new());
+    }
+
+    let mut backups = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        let parsed = path
+            .file_stem()
+            .and_then(|s| s.to_str())
+            .and_then(parse_stem);
+
+        if let Some((created_at, counter)) = parsed {
+            let info = BackupInfo {
+                path: path.to_string_lossy().to_string(),
+                created_at,
+                size: fs::metadata(&path)?.len(),
+            };
+            backups.push((counter, info));
+        }
+    }
+
+    backups.sort_by_key(|(counter, b)| std::cmp::Reverse((b.created_at, *counter)));
+    Ok(backups.into_iter().map(|(_, b)| b).collect())
+}
+
+/// Deletes the oldest snapshots so that at most `keep` remain.
+pub fn rotate_snapshots(library_path: &str, keep: usize) -> anyhow::Result<()> {
+    for backup in list_snapshots(library_path)?.into_iter().skip(keep) {
+        fs::remove_file(&backup.path)?;
+    }
+    Ok(())
+}
+
+/// Settings saved with a snapshot, None for archives made without a config.
+pub fn snapshot_config(archive_path: &Path) -> anyhow::Result<Option<Config>> {
+    let mut archive = zip::ZipArchive::new(File::open(archive_path)?)?;
+    let entry = match archive.by_name(CONFIG_ENTRY) {
+        Ok(entry) => entry,
+        Err(zip::result::ZipError::FileNotFound) => return Ok(None),
+        Err(e) => return Err(e.into()),
+    };
+    let config = serde_yaml::from_reader(entry)
+        .map_err(|e| anyhow::anyhow!("Backup config is invalid: {}", e))?;
+    Ok(Some(config))
+}
+
+/// Extracts the database from a snapshot archive, checks that it is intact and
+/// was not written by a newer version of the app, and migrates it forward.
+async fn extract_and_validate(archive_path: &Path, dest: &Path) -> anyhow::Result<()> {
+    let mut archive = zip::ZipArchive::new(File::open(archive_path)?)?;
+    {
+        let mut entry = archive
+            .by_name(DB_ENTRY)
+            .map_err(|_| anyhow::anyhow!("Backup archive does not contain {}", DB_ENTRY))?;
+        let mut out = File::create(dest)?;
+        std::io::copy(&mut entry, &mut out)?;
+    }
+
+    let mut conn = SqliteConnectOptions::new()
+        .filename(dest)
+        .read_only(true)
+        .connect()
+        .await?;
+
+    let (integrity,): (String,) = sqlx::query_as("PRAGMA integrity_check")
+        .fetch_one(&mut conn)
+        .await?;
+    if integrity != "ok" {
+        return Err(anyhow::anyhow!("Backup database is corrupt: {}", integrity));
+    }
+
+    let version: Option<i64> =
+        sqlx::query_scalar("SELECT MAX(version) FROM _sqlx_migrations WHERE success = 1")
+            .fetch_one(&mut conn)
+            .await
+            .map_err(|_| anyhow::anyhow!("Backup database has no migration history"))?;
+    conn.close().await?;
+
+    match version {
+        Some(v) if v > db::schema_version() => {
+            return Err(anyhow::anyhow!(
+                "Backup was made by a newer version of Cue (schema {}, this build supports {})",
+                v,
+                db::schema_version()
+            ))
+        }
+        Some(_) => {}
+        None => return Err(anyhow::anyhow!("Backup database has no migration history")),
+    }
+
+    // Migrating here means reopening the library after the swap can't fail on an old schema
+    let mut conn = SqliteConnectOptions::new()
+        .filename(dest)
+        .journal_mode(SqliteJournalMode::Delete)
+        .connect()
+        .await?;
+    db::MIGRATOR.run(&mut conn).await?;
+    conn.close().await?;
+    Ok(())
+}
+
+/// `songs.db` and the journal files SQLite keeps next to it.
+fn db_files(dir: &Path, name: &str) -> [PathBuf; 3] {
+    ["", "-wal", "-shm"].map(|suffix| dir.join(format!("{}{}", name, suffix)))
+}
+
+/// Moves whichever of `from` exist onto the matching `to` paths.
+fn rename_all(from: &[PathBuf; 3], to: &[PathBuf; 3]) -> std::io::Result<()> {
+    for (from, to) in from.iter().zip(to) {
+        if from.exists() {
+            fs::rename(from, to)?;
+        }
+    }
+    Ok(())
+}
+
+fn remove_all(paths: &[PathBuf; 3]) {
+    for path in paths {
+        let _ = fs::remove_file(path);
+    }
+}
+
+/// Replaces the library database with the one in `archive_path` and opens it.
+/// Older snapshots are migrated forward first. The snapshot is prepared next to
+/// `songs.db` and renamed into place, and on any failure after `db`'s pool is
+/// closed the previous database is put back for the caller to reopen.
+pub async fn restore_snapshot(db: &Database, archive_path: &Path) -> anyhow::Result<Database> {
+    let dir = Path::new(&db.library_path);
+    let live = db_files(dir, DB_ENTRY);
+    let staged = db_files(dir, &format!("{}.restore", DB_ENTRY));
+    let previous = db_files(dir, &format!("{}.previous", DB_ENTRY));
+    remove_all(&staged);
+    remove_all(&previous);
+
+    if let Err(e) = extract_and_validate(archive_path, &staged[0]).await {
+        remove_all(&staged);
+        return Err(e);
+    }
+
+    // Keep a snapshot of the current state so the restore itself can be reverted
+    if let Err(e) = create_snapshot(db).await {
+        remove_all(&staged);
+        return Err(e);
+    }
+
+    db.pool.close().await;
+
+    let swapped = rename_all(&live, &previous).and_then(|_| fs::rename(&staged[0], &live[0]));
+    let reopened = match swapped {
+        Ok(()) => Database::open(&db.library_path).await,
+        Err(e) => Err(e.into()),
+    };
+
+    match reopened {
+        Ok(restored) => {
+            remove_all(&previous);
+            Ok(restored)
+        }
+        Err(e) => {
+            remove_all(&live);
+            if let Err(rollback) = rename_all(&previous, &live) {
+                eprintln!("Failed to put the previous database back: {}", rollback);
+            }
+            remove_all(&staged);
+            Err(e)
+        }
+    }
+}
+
+async fn run_scheduled_backup(app: &AppHandle) -> anyhow::Result<()> {
+    let (interval_hours, keep) = {
+        let state = app.state::<ConfigState>();
+        let guard = state.lock().unwrap();
+        match guard.as_ref() {
+            Some(cfg) => (cfg.backup_interval_hours, cfg.backup_keep),
+            None => return Ok(()),
+        }
+    };
+    if interval_hours == 0 {
+        return Ok(());
+    }
+
+    let db = {
+        let state = app.state::<DbState>();
+        let guard = state.lock().unwrap();
+        match guard.clone() {
+            Some(db) => db,
+            None => return Ok(()),
+        }
+    };
+
+    let latest = list_snapshots(&db.library_path)?
+        .first()
+        .map(|b| b.created_at)
+        .unwrap_or_default();
+    if unix_now() - latest < interval_hours as i64 * 60 * 60 {
+        return Ok(());
+    }
+
+    create_snapshot(&db).await?;
+    rotate_snapshots(&db.library_path, keep.max(1) as usize)
+}
+
+/// Periodically snapshots the library according to the interval set in `Config`.
+pub fn spawn_scheduler(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        loop {
+            if let Err(e) = run_scheduled_backup(&app).await {
+                eprintln!("Scheduled backup failed: {}", e);
+            }
+            tokio::time::sleep(SCHEDULER_TICK).await;
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::entities::Song;
+
+    async fn open_library(dir: &Path) -> Database {
+        Database::open(&dir.to_string_lossy()).await.unwrap()
+    }
+
+    fn song(id: &str) -> Song {
+        Song {
+            id: id.to_string(),
+            title: format!("Song {}", id),
+            artist: "Artist".to_string(),
+            filename: format!("{}.mp3", id),
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_snapshot_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = open_library(dir.path()).await;
+        db.add_song(&song("1")).await.unwrap();
+
+        let backup = create_snapshot(&db).await.unwrap();
+        db.add_song(&song("2")).await.unwrap();
+
+        let restored = restore_snapshot(&db, Path::new(&backup.path))
+            .await
+            .unwrap();
+        // The safety snapshot taken before restoring doesn't replace the one restored from
+        assert!(Path::new(&backup.path).exists());
+        assert_eq!(list_snapshots(&restored.library_path).unwrap().len(), 2);
+        let songs = restored.get_songs().await.unwrap();
+        assert_eq!(songs.len(), 1);
+        assert_eq!(songs[0].id, "1");
+
+        // Only the live database is left, no staging or previous copies
+        let names: Vec<String> = fs::read_dir(dir.path())
+            .unwrap()
+            .map(|e| e.unwrap().file_name().to_string_lossy().to_string())
+            .filter(|n| n.starts_with(DB_ENTRY))
+            .collect();
+        assert!(names
+            .iter()
+            .all(|n| !n.contains(".restore") && !n.contains(".previous")));
+    }
+
+    #[tokio::test]
+    async fn test_restore_rejects_newer_schema() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = open_library(dir.path()).await;
+        db.add_song(&song("1")).await.unwrap();
+
+        let future = "INSERT INTO _sqlx_migrations (version, description, success, checksum, execution_time) \
+                      VALUES (9999, 'future', 1, x'00', 0)";
+        sqlx::query(future).execute(&db.pool).await.unwrap();
+        let backup = create_snapshot(&db).await.unwrap();
+        sqlx::query("DELETE FROM _sqlx_migrations WHERE version = 9999")
+            .execute(&db.pool)
+            .await
+            .unwrap();
+        db.add_song(&song("2")).await.unwrap();
+
+        let error = match restore_snapshot(&db, Path::new(&backup.path)).await {
+            Ok(_) => panic!("restored a backup from a newer schema"),
+            Err(e) => e.to_string(),
+        };
+        assert!(error.contains("newer version"));
+
+        // The live database was never closed or replaced
+        assert!(!db.pool.is_closed());
+        assert_eq!(db.get_songs().await.unwrap().len(), 2);
+        assert!(!dir.path().join("songs.db.restore").exists());
+    }
+
+    #[test]
+    fn test_archive_names() {
+        let library = tempfile::tempdir().unwrap();
+        let dir = backup_dir(&library.path().to_string_lossy());
+        fs::create_dir_all(&dir).unwrap();
+
+        let (first, _) = create_archive(&dir, 100).unwrap();
+        let (second, _) = create_archive(&dir, 100).unwrap();
+        let (later, _) = create_archive(&dir, 101).unwrap();
+        assert_ne!(first, second);
+
+        // Newest first, same-second snapshots by the order they were taken in
+        let paths: Vec<PathBuf> = list_snapshots(&library.path().to_string_lossy())
+            .unwrap()
+            .into_iter()
+            .map(|b| PathBuf::from(b.path))
+            .collect();
+        assert_eq!(paths, [later, second, first]);
+    }
+}
@@ -191,6 +191,35 @@ fn extract_tar_xz(
     Ok(())
 }
 
+fn bin_path(app: &AppHandle, name: &str) -> Result<PathBuf, anyhow::Error> {
+    let app_data_dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| anyhow::anyhow!("Failed to get app data dir: {}", e))?;
+    let file_name = if cfg!(target_os = "windows") {
+        format!("{}.exe", name)
+    } else {
+        name.to_string()
+    };
+
+    let path = app_data_dir.join("bin").join(file_name);
+    if !path.exists() {
+        return Err(anyhow::anyhow!(
+            "{} not found. Please ensure initialization completed successfully.",
+            name
+        ));
+    }
+    Ok(path)
+}
+
+pub fn ffmpeg_path(app: &AppHandle) -> Result<PathBuf, anyhow::Error> {
+    bin_path(app, "ffmpeg")
+}
+
+pub fn ffprobe_path(app: &AppHandle) -> Result<PathBuf, anyhow::Error> {
+    bin_path(app, "ffprobe")
+}
+
 pub fn check_health(app: &AppHandle) -> bool {
     let app_data_dir = match app.path().app_data_dir() {
         Ok(d) => d,
@@ -8,7 +8,7 @@ pub mod ytdlp;
 
 pub use bun::ensure_bun;
 pub use ejs::ensure_ejs;
-pub use ffmpeg::ensure_ffmpeg;
+pub use ffmpeg::{ensure_ffmpeg, ffmpeg_path, ffprobe_path};
 pub use ytdlp::ensure_ytdlp;
 
 const YT_DLP_VERSION: &str = "2026.02.04";
@@ -16,6 +16,10 @@ const FFMPEG_VERSION: &str = "8.1.2";
 const BUN_VERSION: &str = "1.3.14";
 const EJS_VERSION: &str = "0.8.0";
 
+/// Process creation flag that keeps the bundled tools from opening a console window.
+#[cfg(windows)]
+pub const CREATE_NO_WINDOW: u32 = 0x08000000;
+
 pub fn check_bundler_health(app: &AppHandle) -> bool {
     ytdlp::check_health(app)
         && ffmpeg::check_health(app)
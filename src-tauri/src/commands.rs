@@ -1,11 +1,21 @@
 use tauri::{command, AppHandle, Emitter, Manager, State};
 
+use crate::backup::{self, BackupInfo};
 use crate::bundler;
 use crate::config::{self, Config, ConfigState};
-use crate::db::entities::Song;
+use crate::db::entities::{AuditEntry, Song, SongPage, SongQuery};
 use crate::db::Database;
 use crate::db::DbState;
 use crate::download::{self, DownloadJob, MetadataPayload};
+use crate::filename;
+use crate::hashing::{self, VerifyResult};
+use crate::integrity::{self, Fix, Issue, RepairReport};
+use crate::quality;
+use crate::rekordbox;
+use crate::relink::{self, RelinkProposal};
+use crate::relocate;
+use crate::tagging;
+use crate::trash;
 
 // --- Config Commands ---
 
@@ -18,9 +28,41 @@ pub async fn get_config(state: State<'_, ConfigState>) -> Result<Option<Config>,
 #[command]
 pub async fn update_config(
     state: State<'_, ConfigState>,
+    db_state: State<'_, DbState>,
+    manager: State<'_, download::DownloadManager>,
     app: AppHandle,
-    new_config: Config,
+    mut new_config: Config,
 ) -> Result<(), String> {
+    filename::validate(&new_config.filename_template)?;
+    quality::validate(
+        &new_config.quality_profiles,
+        &new_config.default_quality_profile,
+    )?;
+    new_config.sync_active_profile();
+
+    let library_changed = {
+        let config = state.lock().unwrap();
+        config
+            .as_ref()
+            .is_some_and(|c| c.library_path != new_config.library_path)
+    };
+
+    let _switch = if library_changed {
+        Some(manager.pause_for_switch(&app)?)
+    } else {
+        None
+    };
+
+    // Point the database at the library in the new folder (use relocate_library to move files)
+    let new_db = if library_changed {
+        let db = Database::open(&new_config.library_path)
+            .await
+            .map_err(|e| format!("Failed to initialize database: {}", e))?;
+        Some(db)
+    } else {
+        None
+    };
+
     // Save to file
     config::save_config(&new_config)?;
 
@@ -29,6 +71,15 @@ pub async fn update_config(
         let mut config = state.lock().unwrap();
         *config = Some(new_config.clone());
     }
+    if let Some(new_db) = new_db {
+        {
+            let mut db_entry = db_state.lock().unwrap();
+            *db_entry = Some(new_db.clone());
+        }
+        if let Err(e) = manager.load_library(&app, &new_db).await {
+            eprintln!("Warning: Failed to restore download queue: {}", e);
+        }
+    }
 
     // Ensure dependencies
     bundler::ensure_ytdlp(&app)
@@ -56,25 +107,20 @@ pub async fn initialize_setup(
     app: AppHandle,
     library_path: String,
 ) -> Result<(), String> {
-    let config = Config {
+    let mut config = Config {
         library_path: library_path.clone(),
-        auto_update: true,
+        ..Default::default()
     };
+    config.sync_active_profile();
 
     // Save config
     config::save_config(&config)?;
 
     // Initialize DB
-    let db_path = format!("sqlite:{}/songs.db", library_path);
-    let pool = crate::db::init_db(&db_path)
+    let db_instance = Database::open(&library_path)
         .await
         .map_err(|e| format!("Failed to initialize database: {}", e))?;
 
-    let db_instance = Database {
-        pool,
-        library_path: library_path.clone(),
-    };
-
     // Update states
     {
         let mut cfg = cfg_state.lock().unwrap();
@@ -104,38 +150,45 @@ pub async fn initialize_setup(
 // --- DB Commands ---
 
 #[command]
-pub async fn remove_song(
-    db_state: State<'_, DbState>,
-    cfg_state: State<'_, ConfigState>,
-    id: String,
-) -> Result<(), String> {
+pub async fn remove_song(db_state: State<'_, DbState>, id: String) -> Result<(), String> {
     let db = {
         let db_guard = db_state.lock().unwrap();
         db_guard.clone().ok_or("Database not initialized")?
     };
 
-    let song = db
-        .get_song_by_id(&id)
+    trash::move_to_trash(&db, &id)
         .await
-        .map_err(|e| e.to_string())?
-        .ok_or_else(|| "Song not found".to_string())?;
+        .map_err(|e| e.to_string())
+}
 
-    let library_path = {
-        let config_guard = cfg_state.lock().unwrap();
-        let config = config_guard.as_ref().ok_or("Config not initialized")?;
-        config.library_path.clone()
+#[command]
+pub async fn restore_song(db_state: State<'_, DbState>, id: String) -> Result<(), String> {
+    let db = {
+        let db_guard = db_state.lock().unwrap();
+        db_guard.clone().ok_or("Database not initialized")?
     };
 
-    let file_path = std::path::Path::new(&library_path)
-        .join("Songs")
-        .join(&song.filename);
+    trash::restore(&db, &id).await.map_err(|e| e.to_string())
+}
 
-    // Delete the file from the filesystem
-    if let Err(e) = std::fs::remove_file(&file_path) {
-        eprintln!("Failed to delete file {}: {}", file_path.display(), e);
-    }
+#[command]
+pub async fn get_trash(db_state: State<'_, DbState>) -> Result<Vec<Song>, String> {
+    let db = {
+        let db_guard = db_state.lock().unwrap();
+        db_guard.clone().ok_or("Database not initialized")?
+    };
 
-    db.remove_song(&id).await.map_err(|e| e.to_string())
+    db.get_trashed_songs().await.map_err(|e| e.to_string())
+}
+
+#[command]
+pub async fn empty_trash(db_state: State<'_, DbState>) -> Result<usize, String> {
+    let db = {
+        let db_guard = db_state.lock().unwrap();
+        db_guard.clone().ok_or("Database not initialized")?
+    };
+
+    trash::empty(&db).await.map_err(|e| e.to_string())
 }
 
 #[command]
@@ -147,6 +200,18 @@ pub async fn get_songs(state: State<'_, DbState>) -> Result<Vec<Song>, String> {
     db.get_songs().await.map_err(|e| e.to_string())
 }
 
+#[command]
+pub async fn get_songs_page(
+    state: State<'_, DbState>,
+    query: SongQuery,
+) -> Result<SongPage, String> {
+    let db = {
+        let db_guard = state.lock().unwrap();
+        db_guard.clone().ok_or("Database not initialized")?
+    };
+    db.get_songs_page(&query).await.map_err(|e| e.to_string())
+}
+
 #[command]
 pub async fn search_songs(state: State<'_, DbState>, query: String) -> Result<Vec<Song>, String> {
     let db = {
@@ -176,20 +241,40 @@ pub async fn get_metadata(app: AppHandle, url: String) -> Result<Vec<MetadataPay
 pub async fn add_to_queue(
     app: AppHandle,
     manager: State<'_, download::DownloadManager>,
+    cfg_state: State<'_, ConfigState>,
     url: String,
     id: String,
-    metadata: MetadataPayload,
+    mut metadata: MetadataPayload,
+    options: Option<download::QueueOptions>,
 ) -> Result<(), String> {
-    let job = DownloadJob {
-        id,
-        title: format!("{} - {}", metadata.artist, metadata.title),
-        progress: 0.0,
-        status: "queued".into(),
-        detailed_status: None,
-        url,
-        metadata,
-        logs: Vec::new(),
-    };
+    let options = options.unwrap_or_default();
+    if let Some(ref name) = options.profile {
+        let cfg = cfg_state.lock().unwrap();
+        let config = cfg.as_ref().ok_or("Config not initialized")?;
+        if !config.quality_profiles.iter().any(|p| &p.name == name) {
+            return Err(format!("Quality profile {} not found", name));
+        }
+    }
+
+    if options.split_chapters {
+        if options.start.is_some() || options.end.is_some() {
+            return Err("Cannot split a time range into chapters".into());
+        }
+        metadata.id = id;
+        for track in download::chapters::split(&metadata)? {
+            let mut job = DownloadJob::new(track.id.clone(), url.clone(), track);
+            job.profile = options.profile.clone();
+            manager.add_job(&app, job);
+        }
+        return Ok(());
+    }
+
+    metadata.set_clip(options.start, options.end)?;
+    let id = download::clip_id(&id, metadata.clip_start, metadata.clip_end);
+    metadata.id = id.clone();
+
+    let mut job = DownloadJob::new(id, url, metadata);
+    job.profile = options.profile;
     manager.add_job(&app, job);
     Ok(())
 }
@@ -207,7 +292,11 @@ pub async fn remove_download(
     manager: State<'_, download::DownloadManager>,
     id: String,
 ) -> Result<(), String> {
-    manager.remove_job(&app, &id);
+    if let Some(job) = manager.remove_job(&app, &id) {
+        download::remove_partial_download(&app, &job)
+            .await
+            .map_err(|e| e.to_string())?;
+    }
     Ok(())
 }
 
@@ -220,6 +309,68 @@ pub async fn clear_history(
     Ok(())
 }
 
+#[command]
+pub async fn retry_failed_downloads(
+    app: AppHandle,
+    manager: State<'_, download::DownloadManager>,
+) -> Result<usize, String> {
+    Ok(manager.retry_failed(&app))
+}
+
+#[command]
+pub async fn pause_download(
+    app: AppHandle,
+    manager: State<'_, download::DownloadManager>,
+    id: String,
+) -> Result<(), String> {
+    manager.pause_job(&app, &id)
+}
+
+#[command]
+pub async fn resume_download(
+    app: AppHandle,
+    manager: State<'_, download::DownloadManager>,
+    id: String,
+) -> Result<(), String> {
+    manager.resume_job(&app, &id)
+}
+
+#[command]
+pub async fn move_download(
+    app: AppHandle,
+    manager: State<'_, download::DownloadManager>,
+    id: String,
+    direction: download::QueueMove,
+) -> Result<(), String> {
+    manager.move_job(&app, &id, direction)
+}
+
+#[command]
+pub async fn reorder_queue(
+    app: AppHandle,
+    manager: State<'_, download::DownloadManager>,
+    ids: Vec<String>,
+) -> Result<(), String> {
+    manager.reorder(&app, &ids)
+}
+
+#[command]
+pub async fn set_queue_paused(
+    app: AppHandle,
+    manager: State<'_, download::DownloadManager>,
+    paused: bool,
+) -> Result<(), String> {
+    manager.set_queue_paused(&app, paused);
+    Ok(())
+}
+
+#[command]
+pub async fn is_queue_paused(
+    manager: State<'_, download::DownloadManager>,
+) -> Result<bool, String> {
+    Ok(manager.is_queue_paused())
+}
+
 #[command]
 pub async fn clear_queue(
     app: AppHandle,
@@ -231,10 +382,15 @@ pub async fn clear_queue(
 
 #[command]
 pub async fn cancel_download(
+    app: AppHandle,
     manager: State<'_, download::DownloadManager>,
     id: String,
 ) -> Result<(), String> {
-    manager.cancel_job(&id);
+    if let Some(job) = manager.cancel_job(&app, &id) {
+        download::remove_partial_download(&app, &job)
+            .await
+            .map_err(|e| e.to_string())?;
+    }
     Ok(())
 }
 
@@ -312,56 +468,74 @@ pub async fn sync_song(
     db_state: State<'_, DbState>,
     manager: State<'_, download::DownloadManager>,
     id: String,
+) -> Result<(), String> {
+    let db = {
+        let db_guard = db_state.lock().unwrap();
+        db_guard.clone().ok_or("Database not initialized")?
+    };
+
+    queue_resync(&app, &manager, &db, &id).await
+}
+
+async fn queue_resync(
+    app: &AppHandle,
+    manager: &download::DownloadManager,
+    db: &Database,
+    id: &str,
 ) -> Result<(), String> {
     // Guard: don't re-queue if already active
-    let active_statuses = ["queued", "pending", "downloading"];
     {
         let jobs = manager.get_jobs();
-        if jobs
-            .iter()
-            .any(|j| j.id == id && active_statuses.contains(&j.status.as_str()))
-        {
+        if jobs.iter().any(|j| {
+            j.id == id
+                && (j.status.is_active()
+                    || matches!(
+                        j.status,
+                        download::JobStatus::Queued | download::JobStatus::Paused
+                    ))
+        }) {
             return Err("Song is already queued for download".into());
         }
     }
 
-    let db = {
-        let db_guard = db_state.lock().unwrap();
-        db_guard.clone().ok_or("Database not initialized")?
-    };
-
     let song = db
-        .get_song_by_id(&id)
+        .get_song_by_id(id)
         .await
         .map_err(|e| e.to_string())?
         .ok_or_else(|| "Song not found".to_string())?;
 
-    let url = song.source_url.ok_or_else(|| {
+    let url = song.source_url.clone().ok_or_else(|| {
         "Source URL is missing for this song. It cannot be re-synced automatically.".to_string()
     })?;
 
     let metadata = download::MetadataPayload {
-        id: id.clone(),
+        id: id.to_string(),
         url: url.clone(),
         title: song.title.clone(),
         artist: song.artist.clone(),
         album: song.album.clone(),
         thumbnail: None,
-        duration: None,
+        duration: song.duration,
+        version: song.version.clone(),
+        remixer: song.remixer.clone(),
+        featured_artists: song.featured_artists.clone(),
+        // Re-downloads cut the same section as the first download
+        clip_start: song.clip_start,
+        clip_end: song.clip_end,
+        chapters: Vec::new(),
+        track_number: song.track_number,
     };
 
-    let job = DownloadJob {
-        id: id.clone(),
-        title: format!("{} - {}", metadata.artist, metadata.title),
-        progress: 0.0,
-        status: "queued".into(),
-        detailed_status: None,
-        url,
-        metadata,
-        logs: Vec::new(),
+    // Re-downloaded in the format it was first downloaded in
+    let profile = {
+        let cfg_state = app.state::<ConfigState>();
+        let config = cfg_state.lock().unwrap();
+        config.as_ref().and_then(|c| c.redownload_profile(&song))
     };
 
-    manager.add_job(&app, job);
+    let mut job = DownloadJob::new(id.to_string(), url, metadata);
+    job.profile = profile;
+    manager.add_job(app, job);
     Ok(())
 }
 
@@ -382,3 +556,629 @@ pub async fn update_song_tags(
 
     Ok(())
 }
+
+#[command]
+pub async fn update_song_rating(
+    db_state: State<'_, DbState>,
+    ids: Vec<String>,
+    rating: u8,
+) -> Result<(), String> {
+    if rating > 5 {
+        return Err("Rating must be between 0 and 5".into());
+    }
+
+    let db = {
+        let db_guard = db_state.lock().unwrap();
+        db_guard.clone().ok_or("Database not initialized")?
+    };
+
+    db.update_song_rating(&ids, rating)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[command]
+pub async fn update_song_colour(
+    db_state: State<'_, DbState>,
+    ids: Vec<String>,
+    colour: Option<String>,
+) -> Result<(), String> {
+    let colour = match colour {
+        Some(c) => Some(rekordbox::colour_label(&c).ok_or(format!("Unknown colour label: {}", c))?),
+        None => None,
+    };
+
+    let db = {
+        let db_guard = db_state.lock().unwrap();
+        db_guard.clone().ok_or("Database not initialized")?
+    };
+
+    db.update_song_colour(&ids, colour)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[command]
+pub async fn update_song_comments(
+    db_state: State<'_, DbState>,
+    ids: Vec<String>,
+    comments: Option<String>,
+) -> Result<(), String> {
+    let db = {
+        let db_guard = db_state.lock().unwrap();
+        db_guard.clone().ok_or("Database not initialized")?
+    };
+
+    db.update_song_comments(&ids, comments.as_deref())
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[command]
+pub async fn update_song_credits(
+    app: AppHandle,
+    db_state: State<'_, DbState>,
+    id: String,
+    version: Option<String>,
+    remixer: Option<String>,
+    featured_artists: Option<String>,
+) -> Result<(), String> {
+    let db = {
+        let db_guard = db_state.lock().unwrap();
+        db_guard.clone().ok_or("Database not initialized")?
+    };
+
+    db.update_song_credits(
+        &id,
+        version.as_deref(),
+        remixer.as_deref(),
+        featured_artists.as_deref(),
+    )
+    .await
+    .map_err(|e| e.to_string())?;
+
+    let song = db
+        .get_song_by_id(&id)
+        .await
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| "Song not found".to_string())?;
+    let path = std::path::Path::new(&db.library_path)
+        .join("Songs")
+        .join(&song.filename);
+    if path.exists() {
+        tagging::write_tags(&app, &path, &song)
+            .await
+            .map_err(|e| e.to_string())?;
+        rehash_song(&db, &id).await?;
+    }
+    Ok(())
+}
+
+#[command]
+pub async fn update_song_play_count(
+    db_state: State<'_, DbState>,
+    ids: Vec<String>,
+    play_count: i64,
+) -> Result<(), String> {
+    if play_count < 0 {
+        return Err("Play count cannot be negative".into());
+    }
+
+    let db = {
+        let db_guard = db_state.lock().unwrap();
+        db_guard.clone().ok_or("Database not initialized")?
+    };
+
+    db.update_song_play_count(&ids, play_count)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[command]
+pub async fn increment_play_count(db_state: State<'_, DbState>, id: String) -> Result<(), String> {
+    let db = {
+        let db_guard = db_state.lock().unwrap();
+        db_guard.clone().ok_or("Database not initialized")?
+    };
+
+    db.increment_play_count(&id)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[command]
+pub async fn get_history(
+    db_state: State<'_, DbState>,
+    song_id: Option<String>,
+    limit: Option<i64>,
+) -> Result<Vec<AuditEntry>, String> {
+    let db = {
+        let db_guard = db_state.lock().unwrap();
+        db_guard.clone().ok_or("Database not initialized")?
+    };
+
+    db.get_history(song_id.as_deref(), limit.unwrap_or(100))
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[command]
+pub async fn undo_last_change(
+    app: AppHandle,
+    db_state: State<'_, DbState>,
+) -> Result<Vec<AuditEntry>, String> {
+    let db = {
+        let db_guard = db_state.lock().unwrap();
+        db_guard.clone().ok_or("Database not initialized")?
+    };
+
+    let entries = db.undo_last_change().await.map_err(|e| e.to_string())?;
+
+    // Undoing a trash, restore, rename or add also has to move the file
+    for entry in &entries {
+        if let Err(e) = trash::reconcile(&db, entry).await {
+            eprintln!("Failed to move file for {}: {}", entry.song_id, e);
+        }
+    }
+
+    let _ = app.emit("library://updated", ());
+    Ok(entries)
+}
+
+#[command]
+pub async fn create_backup(
+    db_state: State<'_, DbState>,
+    cfg_state: State<'_, ConfigState>,
+) -> Result<BackupInfo, String> {
+    let db = {
+        let db_guard = db_state.lock().unwrap();
+        db_guard.clone().ok_or("Database not initialized")?
+    };
+
+    let keep = {
+        let config_guard = cfg_state.lock().unwrap();
+        let config = config_guard.as_ref().ok_or("Config not initialized")?;
+        config.backup_keep
+    };
+
+    let info = backup::create_snapshot(&db)
+        .await
+        .map_err(|e| e.to_string())?;
+    backup::rotate_snapshots(&db.library_path, keep.max(1) as usize).map_err(|e| e.to_string())?;
+
+    Ok(info)
+}
+
+#[command]
+pub async fn list_backups(db_state: State<'_, DbState>) -> Result<Vec<BackupInfo>, String> {
+    let db = {
+        let db_guard = db_state.lock().unwrap();
+        db_guard.clone().ok_or("Database not initialized")?
+    };
+
+    backup::list_snapshots(&db.library_path).map_err(|e| e.to_string())
+}
+
+#[command]
+pub async fn restore_backup(
+    app: AppHandle,
+    db_state: State<'_, DbState>,
+    cfg_state: State<'_, ConfigState>,
+    manager: State<'_, download::DownloadManager>,
+    path: String,
+) -> Result<(), String> {
+    let _switch = manager.pause_for_switch(&app)?;
+
+    // Read before anything changes, so an unreadable config leaves the library untouched
+    let snapshot_config =
+        backup::snapshot_config(std::path::Path::new(&path)).map_err(|e| e.to_string())?;
+
+    let db = {
+        let db_guard = db_state.lock().unwrap();
+        db_guard.clone().ok_or("Database not initialized")?
+    };
+
+    let restored = match backup::restore_snapshot(&db, std::path::Path::new(&path)).await {
+        Ok(restored) => restored,
+        Err(e) => {
+            // The swap failed after the old pool was closed, reopen the database it put back
+            if db.pool.is_closed() {
+                let reopened = Database::open(&db.library_path)
+                    .await
+                    .map_err(|e| format!("Failed to reopen the library database: {}", e))?;
+                *db_state.lock().unwrap() = Some(reopened.clone());
+                if let Err(e) = manager.load_library(&app, &reopened).await {
+                    eprintln!("Warning: Failed to restore download queue: {}", e);
+                }
+            }
+            return Err(format!("Failed to restore backup: {}", e));
+        }
+    };
+
+    restored.trigger_rekordbox_export().await;
+
+    {
+        let mut db_entry = db_state.lock().unwrap();
+        *db_entry = Some(restored.clone());
+    }
+    if let Some(snapshot_config) = snapshot_config {
+        let restored_config = {
+            let mut config_guard = cfg_state.lock().unwrap();
+            config_guard.as_mut().map(|config| {
+                config.restore_settings(snapshot_config);
+                config.clone()
+            })
+        };
+        if let Some(config) = restored_config {
+            config::save_config(&config)?;
+            let _ = app.emit("config://update", config);
+        }
+    }
+    // The snapshot has its own saved queue
+    if let Err(e) = manager.load_library(&app, &restored).await {
+        eprintln!("Warning: Failed to restore download queue: {}", e);
+    }
+    hashing::spawn_backfill(&restored);
+
+    let _ = app.emit("library://updated", ());
+    Ok(())
+}
+
+#[command]
+pub async fn relocate_library(
+    app: AppHandle,
+    db_state: State<'_, DbState>,
+    cfg_state: State<'_, ConfigState>,
+    manager: State<'_, download::DownloadManager>,
+    new_path: String,
+    move_files: bool,
+) -> Result<(), String> {
+    let _switch = manager.pause_for_switch(&app)?;
+
+    let db = {
+        let db_guard = db_state.lock().unwrap();
+        db_guard.clone().ok_or("Database not initialized")?
+    };
+
+    let mut new_config = {
+        let config_guard = cfg_state.lock().unwrap();
+        config_guard.clone().ok_or("Config not initialized")?
+    };
+    new_config.library_path = new_path.clone();
+    new_config.sync_active_profile();
+
+    let new_db = relocate::copy_to(&app, &db, std::path::Path::new(&new_path))
+        .await
+        .map_err(|e| format!("Failed to relocate library: {}", e))?;
+
+    if let Err(e) = config::save_config(&new_config) {
+        relocate::discard(&new_db).await;
+        return Err(e);
+    }
+
+    {
+        let mut cfg = cfg_state.lock().unwrap();
+        *cfg = Some(new_config.clone());
+    }
+    {
+        let mut db_entry = db_state.lock().unwrap();
+        *db_entry = Some(new_db.clone());
+    }
+    if let Err(e) = manager.load_library(&app, &new_db).await {
+        eprintln!("Warning: Failed to restore download queue: {}", e);
+    }
+    hashing::spawn_backfill(&new_db);
+
+    if move_files {
+        relocate::discard(&db).await;
+    }
+
+    app.emit("config://update", new_config)
+        .map_err(|e| e.to_string())?;
+    let _ = app.emit("library://updated", ());
+    Ok(())
+}
+
+// --- Profile Commands ---
+
+#[command]
+pub async fn create_profile(
+    app: AppHandle,
+    cfg_state: State<'_, ConfigState>,
+    name: String,
+    library_path: String,
+) -> Result<(), String> {
+    let mut config = {
+        let config_guard = cfg_state.lock().unwrap();
+        config_guard.clone().ok_or("Config not initialized")?
+    };
+
+    config.add_profile(&name, &library_path)?;
+    config::save_config(&config)?;
+
+    {
+        let mut cfg = cfg_state.lock().unwrap();
+        *cfg = Some(config.clone());
+    }
+
+    app.emit("config://update", config)
+        .map_err(|e| e.to_string())
+}
+
+#[command]
+pub async fn switch_profile(
+    app: AppHandle,
+    db_state: State<'_, DbState>,
+    cfg_state: State<'_, ConfigState>,
+    manager: State<'_, download::DownloadManager>,
+    name: String,
+) -> Result<(), String> {
+    let _switch = manager.pause_for_switch(&app)?;
+
+    let mut config = {
+        let config_guard = cfg_state.lock().unwrap();
+        config_guard.clone().ok_or("Config not initialized")?
+    };
+
+    if config.active_profile == name {
+        return Ok(());
+    }
+    config.set_active_profile(&name)?;
+
+    let new_db = Database::open(&config.library_path)
+        .await
+        .map_err(|e| format!("Failed to open library: {}", e))?;
+
+    config::save_config(&config)?;
+
+    let old_db = {
+        let mut db_entry = db_state.lock().unwrap();
+        db_entry.replace(new_db.clone())
+    };
+    {
+        let mut cfg = cfg_state.lock().unwrap();
+        *cfg = Some(config.clone());
+    }
+
+    // The old library gets its pending queue writes before it's closed
+    if let Err(e) = manager.load_library(&app, &new_db).await {
+        eprintln!("Warning: Failed to restore download queue: {}", e);
+    }
+    if let Some(old_db) = old_db {
+        old_db.pool.close().await;
+    }
+    hashing::spawn_backfill(&new_db);
+
+    app.emit("config://update", config)
+        .map_err(|e| e.to_string())?;
+    let _ = app.emit("library://updated", ());
+    Ok(())
+}
+
+#[command]
+pub async fn delete_profile(
+    app: AppHandle,
+    cfg_state: State<'_, ConfigState>,
+    name: String,
+) -> Result<(), String> {
+    let mut config = {
+        let config_guard = cfg_state.lock().unwrap();
+        config_guard.clone().ok_or("Config not initialized")?
+    };
+
+    // Only the profile entry is removed, the library folder is left untouched
+    config.remove_profile(&name)?;
+    config::save_config(&config)?;
+
+    {
+        let mut cfg = cfg_state.lock().unwrap();
+        *cfg = Some(config.clone());
+    }
+
+    app.emit("config://update", config)
+        .map_err(|e| e.to_string())
+}
+
+// --- Integrity Commands ---
+
+#[command]
+pub async fn check_library_integrity(
+    app: AppHandle,
+    db_state: State<'_, DbState>,
+) -> Result<Vec<Issue>, String> {
+    let db = {
+        let db_guard = db_state.lock().unwrap();
+        db_guard.clone().ok_or("Database not initialized")?
+    };
+
+    integrity::scan(&app, &db).await.map_err(|e| e.to_string())
+}
+
+#[command]
+pub async fn repair_library(
+    app: AppHandle,
+    db_state: State<'_, DbState>,
+    manager: State<'_, download::DownloadManager>,
+    fixes: Vec<Fix>,
+) -> Result<RepairReport, String> {
+    let db = {
+        let db_guard = db_state.lock().unwrap();
+        db_guard.clone().ok_or("Database not initialized")?
+    };
+
+    let mut report = RepairReport {
+        applied: 0,
+        errors: Vec::new(),
+    };
+
+    for fix in fixes {
+        let result = match fix {
+            Fix::Redownload { ref song_id } => queue_resync(&app, &manager, &db, song_id).await,
+            Fix::TrashSong { ref song_id } => trash::move_to_trash(&db, song_id)
+                .await
+                .map_err(|e| e.to_string()),
+            Fix::ImportFile { ref filename } => integrity::import_file(&db, filename)
+                .await
+                .map_err(|e| e.to_string()),
+            Fix::TrashEntries { ref song_ids } => trash::trash_entries(&db, song_ids)
+                .await
+                .map_err(|e| e.to_string()),
+            Fix::AcceptHash { ref song_id } => rehash_song(&db, song_id).await,
+        };
+
+        match result {
+            Ok(()) => report.applied += 1,
+            Err(e) => report.errors.push(e),
+        }
+    }
+
+    let _ = app.emit("library://updated", ());
+    Ok(report)
+}
+
+// --- Hashing Commands ---
+
+async fn rehash_song(db: &Database, id: &str) -> Result<(), String> {
+    let song = db
+        .get_song_by_id(id)
+        .await
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| "Song not found".to_string())?;
+
+    let path = std::path::Path::new(&db.library_path)
+        .join("Songs")
+        .join(&song.filename);
+    let (hash, mtime) = hashing::hash_file_async(&path)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    db.update_song_hash(id, &hash, mtime)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[command]
+pub async fn verify_songs(
+    db_state: State<'_, DbState>,
+    ids: Option<Vec<String>>,
+) -> Result<Vec<VerifyResult>, String> {
+    let db = {
+        let db_guard = db_state.lock().unwrap();
+        db_guard.clone().ok_or("Database not initialized")?
+    };
+
+    let songs = match ids {
+        Some(ids) => {
+            let mut songs = Vec::new();
+            for id in ids {
+                if let Some(song) = db.get_song_by_id(&id).await.map_err(|e| e.to_string())? {
+                    songs.push(song);
+                }
+            }
+            songs
+        }
+        None => db.get_songs().await.map_err(|e| e.to_string())?,
+    };
+
+    let mut results = Vec::new();
+    for song in songs {
+        let status = hashing::verify_song(&db, &song)
+            .await
+            .map_err(|e| e.to_string())?;
+        results.push(VerifyResult {
+            song_id: song.id,
+            status,
+        });
+    }
+
+    Ok(results)
+}
+
+#[command]
+pub async fn rehash_songs(db_state: State<'_, DbState>, ids: Vec<String>) -> Result<(), String> {
+    let db = {
+        let db_guard = db_state.lock().unwrap();
+        db_guard.clone().ok_or("Database not initialized")?
+    };
+
+    for id in ids {
+        rehash_song(&db, &id).await?;
+    }
+    Ok(())
+}
+
+#[command]
+pub async fn relink_missing(
+    app: AppHandle,
+    db_state: State<'_, DbState>,
+    cfg_state: State<'_, ConfigState>,
+    search_roots: Vec<String>,
+    apply: bool,
+) -> Result<Vec<RelinkProposal>, String> {
+    let db = {
+        let db_guard = db_state.lock().unwrap();
+        db_guard.clone().ok_or("Database not initialized")?
+    };
+
+    let template = {
+        let config_guard = cfg_state.lock().unwrap();
+        let config = config_guard.as_ref().ok_or("Config not initialized")?;
+        config.filename_template.clone()
+    };
+
+    let proposals = relink::find_missing(&app, &db, &search_roots, &template)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    if apply {
+        relink::apply(&db, &proposals)
+            .await
+            .map_err(|e| e.to_string())?;
+        let _ = app.emit("library://updated", ());
+    }
+
+    Ok(proposals)
+}
+
+#[command]
+pub async fn apply_relinks(
+    app: AppHandle,
+    db_state: State<'_, DbState>,
+    proposals: Vec<RelinkProposal>,
+) -> Result<usize, String> {
+    let db = {
+        let db_guard = db_state.lock().unwrap();
+        db_guard.clone().ok_or("Database not initialized")?
+    };
+
+    let applied = relink::apply(&db, &proposals)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let _ = app.emit("library://updated", ());
+    Ok(applied)
+}
+
+#[command]
+pub async fn rename_songs_to_template(
+    app: AppHandle,
+    config_state: State<'_, ConfigState>,
+    db_state: State<'_, DbState>,
+) -> Result<usize, String> {
+    let template = {
+        let config_guard = config_state.lock().unwrap();
+        let config = config_guard.as_ref().ok_or("Config not initialized")?;
+        config.filename_template.clone()
+    };
+    let db = {
+        let db_guard = db_state.lock().unwrap();
+        db_guard.clone().ok_or("Database not initialized")?
+    };
+
+    let renamed = filename::rename_all(&db, &template)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let _ = app.emit("library://updated", ());
+    Ok(renamed)
+}
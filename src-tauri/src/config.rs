@@ -1,19 +1,92 @@
 use serde::{Deserialize, Serialize};
-use std::{fs, sync::Mutex};
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+use crate::db::entities::Song;
+use crate::quality::{self, AudioFormat, QualityProfile};
 
 pub type ConfigState = Mutex<Option<Config>>;
 
+const DEFAULT_PROFILE: &str = "Default";
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct LibraryProfile {
+    pub name: String,
+    pub library_path: String,
+}
+
 #[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct Config {
+    /// Folder of the active profile's library
     pub library_path: String,
+    #[serde(default)]
+    pub profiles: Vec<LibraryProfile>,
+    #[serde(default = "default_active_profile")]
+    pub active_profile: String,
     #[serde(default = "default_auto_update")]
     pub auto_update: bool,
+    /// Days a removed song stays in the trash before being purged, 0 keeps it forever
+    #[serde(default = "default_trash_retention_days")]
+    pub trash_retention_days: u32,
+    /// Hours between automatic library snapshots, 0 disables them
+    #[serde(default = "default_backup_interval_hours")]
+    pub backup_interval_hours: u32,
+    /// Number of snapshots kept before the oldest ones are rotated out
+    #[serde(default = "default_backup_keep")]
+    pub backup_keep: u32,
+    /// Path of new songs under `Songs/`, e.g. `{artist}/{album}/{title}`
+    #[serde(default = "default_filename_template")]
+    pub filename_template: String,
+    /// Number of downloads that run at the same time
+    #[serde(default = "default_max_concurrent_downloads")]
+    pub max_concurrent_downloads: u32,
+    /// Per-domain caps on concurrent downloads, e.g. `youtube.com: 2`, also applied to subdomains
+    #[serde(default)]
+    pub domain_limits: BTreeMap<String, u32>,
+    /// Output formats a download can be queued with
+    #[serde(default = "quality::builtin_profiles")]
+    pub quality_profiles: Vec<QualityProfile>,
+    /// Name of the profile used when a download doesn't pick one
+    #[serde(default = "default_quality_profile")]
+    pub default_quality_profile: String,
+}
+
+fn default_active_profile() -> String {
+    DEFAULT_PROFILE.to_string()
 }
 
 fn default_auto_update() -> bool {
     true
 }
 
+fn default_trash_retention_days() -> u32 {
+    30
+}
+
+fn default_backup_interval_hours() -> u32 {
+    24
+}
+
+fn default_backup_keep() -> u32 {
+    7
+}
+
+fn default_max_concurrent_downloads() -> u32 {
+    3
+}
+
+fn default_quality_profile() -> String {
+    quality::DEFAULT_PROFILE.to_string()
+}
+
+fn default_filename_template() -> String {
+    crate::filename::DEFAULT_TEMPLATE.to_string()
+}
+
 impl Default for Config {
     fn default() -> Self {
         let library_path = dirs::audio_dir()
@@ -25,17 +98,145 @@ impl Default for Config {
             });
 
         Self {
+            profiles: vec![LibraryProfile {
+                name: default_active_profile(),
+                library_path: library_path.clone(),
+            }],
             library_path,
+            active_profile: default_active_profile(),
             auto_update: true,
+            trash_retention_days: default_trash_retention_days(),
+            backup_interval_hours: default_backup_interval_hours(),
+            backup_keep: default_backup_keep(),
+            filename_template: default_filename_template(),
+            max_concurrent_downloads: default_max_concurrent_downloads(),
+            domain_limits: BTreeMap::new(),
+            quality_profiles: quality::builtin_profiles(),
+            default_quality_profile: default_quality_profile(),
         }
     }
 }
 
-pub fn load_config() -> Result<Option<Config>, String> {
+impl Config {
+    /// Takes the settings saved with a restored snapshot. Where the libraries
+    /// live is kept, the snapshot only covers the library being restored.
+    pub fn restore_settings(&mut self, snapshot: Config) {
+        *self = Config {
+            library_path: std::mem::take(&mut self.library_path),
+            profiles: std::mem::take(&mut self.profiles),
+            active_profile: std::mem::take(&mut self.active_profile),
+            ..snapshot
+        };
+    }
+
+    /// Makes sure the active profile exists and points at `library_path`.
+    /// Configs written before profiles existed get a single default profile.
+    pub fn sync_active_profile(&mut self) {
+        if self.active_profile.is_empty() {
+            self.active_profile = default_active_profile();
+        }
+
+        match self
+            .profiles
+            .iter_mut()
+            .find(|p| p.name == self.active_profile)
+        {
+            Some(profile) => profile.library_path = self.library_path.clone(),
+            None => self.profiles.push(LibraryProfile {
+                name: self.active_profile.clone(),
+                library_path: self.library_path.clone(),
+            }),
+        }
+    }
+
+    pub fn add_profile(&mut self, name: &str, library_path: &str) -> Result<(), String> {
+        let name = name.trim();
+        if name.is_empty() {
+            return Err("Profile name cannot be empty".into());
+        }
+        if self.profiles.iter().any(|p| p.name == name) {
+            return Err(format!("A profile named {} already exists", name));
+        }
+
+        self.profiles.push(LibraryProfile {
+            name: name.to_string(),
+            library_path: library_path.to_string(),
+        });
+        Ok(())
+    }
+
+    pub fn remove_profile(&mut self, name: &str) -> Result<(), String> {
+        if name == self.active_profile {
+            return Err("Cannot delete the active profile".into());
+        }
+
+        let before = self.profiles.len();
+        self.profiles.retain(|p| p.name != name);
+        if self.profiles.len() == before {
+            return Err(format!("Profile {} not found", name));
+        }
+        Ok(())
+    }
+
+    /// Quality profile called `name`, falling back to the default profile
+    /// when no name is given or the profile has since been removed.
+    pub fn quality_profile(&self, name: Option<&str>) -> QualityProfile {
+        let find = |name: &str| self.quality_profiles.iter().find(|p| p.name == name);
+        name.and_then(find)
+            .or_else(|| find(&self.default_quality_profile))
+            .cloned()
+            .unwrap_or_else(|| quality::builtin_profiles().remove(0))
+    }
+
+    /// Profile to re-download `song` with: the one it was downloaded with while
+    /// it still exists, otherwise one that produces the same kind of file.
+    pub fn redownload_profile(&self, song: &Song) -> Option<String> {
+        let exists = |name: &String| self.quality_profiles.iter().any(|p| &p.name == name);
+        if let Some(name) = song.quality_profile.as_ref().filter(|n| exists(n)) {
+            return Some(name.clone());
+        }
+
+        let ext = Path::new(&song.filename)
+            .extension()?
+            .to_string_lossy()
+            .to_lowercase();
+        let default = self.quality_profile(None);
+        let fixed = [default.clone()]
+            .into_iter()
+            .chain(self.quality_profiles.iter().cloned())
+            .find(|p| p.format.extension() == Some(ext.as_str()));
+        // An extension no fixed format writes, e.g. .m4a, came from the source as is
+        let found = fixed.or_else(|| {
+            self.quality_profiles
+                .iter()
+                .find(|p| p.format == AudioFormat::Original)
+                .cloned()
+        });
+        found.map(|p| p.name).filter(exists)
+    }
+
+    pub fn set_active_profile(&mut self, name: &str) -> Result<(), String> {
+        let profile = self
+            .profiles
+            .iter()
+            .find(|p| p.name == name)
+            .ok_or_else(|| format!("Profile {} not found", name))?;
+
+        self.library_path = profile.library_path.clone();
+        self.active_profile = name.to_string();
+        Ok(())
+    }
+}
+
+pub fn config_path() -> Result<PathBuf, String> {
     let config_dir = dirs::config_dir()
         .ok_or("Could not find config directory")?
         .join("cue");
-    let config_path = config_dir.join("config.yaml");
+    Ok(config_dir.join("config.yaml"))
+}
+
+pub fn load_config() -> Result<Option<Config>, String> {
+    let config_path = config_path()?;
 
     if !config_path.exists() {
         return Ok(None);
@@ -44,21 +245,22 @@ pub fn load_config() -> Result<Option<Config>, String> {
     let config_content = fs::read_to_string(&config_path)
         .map_err(|e| format!("Failed to read config file: {}", e))?;
 
-    let config: Config = serde_yaml::from_str(&config_content)
+    let mut config: Config = serde_yaml::from_str(&config_content)
         .map_err(|e| format!("Failed to parse config file: {}", e))?;
+    config.sync_active_profile();
 
     Ok(Some(config))
 }
 
 pub fn save_config(config: &Config) -> Result<(), String> {
-    let config_dir = dirs::config_dir()
-        .ok_or("Could not find config directory")?
-        .join("cue");
-    let config_path = config_dir.join("config.yaml");
+    let config_path = config_path()?;
+    let config_dir = config_path
+        .parent()
+        .ok_or("Could not find config directory")?;
 
     // Ensuring directory exists is handled by load_config/init but safe to do here if needed
     if !config_dir.exists() {
-        fs::create_dir_all(&config_dir)
+        fs::create_dir_all(config_dir)
             .map_err(|e| format!("Failed to create config directory: {}", e))?;
     }
 
@@ -70,3 +272,77 @@ pub fn save_config(config: &Config) -> Result<(), String> {
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_profiles() {
+        let mut config: Config = serde_yaml::from_str("library_path: /music/club\n").unwrap();
+        config.sync_active_profile();
+        assert_eq!(config.active_profile, "Default");
+        assert_eq!(config.profiles.len(), 1);
+        assert_eq!(config.profiles[0].library_path, "/music/club");
+
+        config.add_profile("Wedding", "/music/wedding").unwrap();
+        assert!(config.add_profile("Wedding", "/elsewhere").is_err());
+
+        config.set_active_profile("Wedding").unwrap();
+        assert_eq!(config.library_path, "/music/wedding");
+        assert!(config.remove_profile("Wedding").is_err());
+
+        config.remove_profile("Default").unwrap();
+        assert_eq!(config.profiles.len(), 1);
+        assert!(config.set_active_profile("Default").is_err());
+    }
+
+    #[test]
+    fn test_quality_profiles() {
+        let mut config: Config = serde_yaml::from_str("library_path: /music/club\n").unwrap();
+        assert_eq!(config.quality_profiles.len(), 5);
+        assert_eq!(config.quality_profile(None).name, "MP3 320");
+        assert_eq!(config.quality_profile(Some("FLAC")).name, "FLAC");
+        assert_eq!(config.quality_profile(Some("Removed")).name, "MP3 320");
+
+        config.default_quality_profile = "AIFF".to_string();
+        assert_eq!(config.quality_profile(None).name, "AIFF");
+        config.quality_profiles.clear();
+        assert_eq!(config.quality_profile(None).name, "MP3 320");
+    }
+
+    #[test]
+    fn test_redownload_profile() {
+        let mut config: Config = serde_yaml::from_str("library_path: /music/club\n").unwrap();
+        let song = |filename: &str, profile: Option<&str>| Song {
+            filename: filename.to_string(),
+            quality_profile: profile.map(str::to_string),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            config.redownload_profile(&song("a.aiff", Some("AIFF"))),
+            Some("AIFF".to_string())
+        );
+        // Songs from before profiles were stored, or whose profile was deleted
+        assert_eq!(
+            config.redownload_profile(&song("a.flac", None)),
+            Some("FLAC".to_string())
+        );
+        assert_eq!(
+            config.redownload_profile(&song("a.aiff", Some("Removed"))),
+            Some("AIFF".to_string())
+        );
+        assert_eq!(
+            config.redownload_profile(&song("a.mp3", None)),
+            Some("MP3 320".to_string())
+        );
+        assert_eq!(
+            config.redownload_profile(&song("a.m4a", None)),
+            Some("Keep original".to_string())
+        );
+
+        config.quality_profiles.retain(|p| p.name == "MP3 320");
+        assert_eq!(config.redownload_profile(&song("a.m4a", None)), None);
+    }
+}
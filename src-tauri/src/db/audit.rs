@@ -0,0 +1,179 @@
+use sqlx::SqliteConnection;
+
+use super::entities::{AuditEntry, Song};
+use super::Database;
+use crate::trash;
+
+fn actor() -> Option<String> {
+    std::env::var("USER")
+        .or_else(|_| std::env::var("USERNAME"))
+        .ok()
+}
+
+fn to_json(song: Option<&Song>) -> Result<Option<String>, sqlx::Error> {
+    song.map(serde_json::to_string)
+        .transpose()
+        .map_err(|e| sqlx::Error::Encode(Box::new(e)))
+}
+
+/// Audited writes take the write lock up front, so concurrent ones wait for each
+/// other instead of reading the same next change id and failing with SQLITE_BUSY.
+pub(super) const BEGIN_WRITE: &str = "BEGIN IMMEDIATE";
+
+pub(super) async fn next_change_id(conn: &mut SqliteConnection) -> Result<i64, sqlx::Error> {
+    let (change_id,): (i64,) = sqlx::query_as(include_str!("../../queries/next_change_id.sql"))
+        .fetch_one(conn)
+        .await?;
+    Ok(change_id)
+}
+
+pub(super) async fn snapshot(
+    conn: &mut SqliteConnection,
+    id: &str,
+) -> Result<Option<Song>, sqlx::Error> {
+    sqlx::query_as::<_, Song>(include_str!("../../queries/get_song_by_id.sql"))
+        .bind(id)
+        .fetch_optional(conn)
+        .await
+}
+
+/// Writes an audit row for `song_id`, reading its current state as the "after" snapshot.
+pub(super) async fn record(
+    conn: &mut SqliteConnection,
+    change_id: i64,
+    action: &str,
+    song_id: &str,
+    before: Option<&Song>,
+) -> Result<(), sqlx::Error> {
+    let after = snapshot(&mut *conn, song_id).await?;
+    if before.is_none() && after.is_none() {
+        return Ok(());
+    }
+
+    sqlx::query(include_str!("../../queries/add_audit_entry.sql"))
+        .bind(change_id)
+        .bind(song_id)
+        .bind(action)
+        .bind(actor())
+        .bind(to_json(before)?)
+        .bind(to_json(after.as_ref())?)
+        .execute(conn)
+        .await?;
+    Ok(())
+}
+
+async fn write_snapshot(conn: &mut SqliteConnection, song: &Song) -> Result<(), sqlx::Error> {
+    sqlx::query(include_str!("../../queries/restore_song_snapshot.sql"))
+        .bind(&song.id)
+        .bind(&song.title)
+        .bind(&song.artist)
+        .bind(&song.album)
+        .bind(&song.filename)
+        .bind(&song.source_url)
+        .bind(&song.tags)
+        .bind(song.rating)
+        .bind(&song.colour)
+        .bind(&song.comments)
+        .bind(song.play_count)
+        .bind(song.deleted_at)
+        .bind(&song.content_hash)
+        .bind(song.file_mtime)
+        .bind(song.duration)
+        .bind(song.created_at)
+        .bind(song.updated_at)
+        .bind(song.downloaded_at)
+        .bind(&song.version)
+        .bind(&song.remixer)
+        .bind(&song.featured_artists)
+        .bind(song.clip_start)
+        .bind(song.clip_end)
+        .bind(song.track_number)
+        .bind(&song.quality_profile)
+        .execute(conn)
+        .await?;
+    Ok(())
+}
+
+impl Database {
+    pub async fn get_history(
+        &self,
+        song_id: Option<&str>,
+        limit: i64,
+    ) -> Result<Vec<AuditEntry>, sqlx::Error> {
+        let entries =
+            sqlx::query_as::<_, AuditEntry>(include_str!("../../queries/get_history.sql"))
+                .bind(song_id)
+                .bind(limit)
+                .fetch_all(&self.pool)
+                .await?;
+        Ok(entries)
+    }
+
+    /// Reverts every song touched by the most recent change that has not been undone yet,
+    /// returning the entries that were rolled back.
+    pub async fn undo_last_change(&self) -> Result<Vec<AuditEntry>, sqlx::Error> {
+        let mut tx = self.pool.begin_with(BEGIN_WRITE).await?;
+
+        let entries =
+            sqlx::query_as::<_, AuditEntry>(include_str!("../../queries/get_last_change.sql"))
+                .fetch_all(&mut *tx)
+                .await?;
+
+        // A purged song can't come back. The change is marked undone anyway so the
+        // ones before it can still be undone.
+        for entry in entries.iter().filter(|e| e.action == "remove") {
+            let Some(ref json) = entry.before else {
+                continue;
+            };
+            let song: Song =
+                serde_json::from_str(json).map_err(|e| sqlx::Error::Decode(Box::new(e)))?;
+            if !trash::has_file(&self.library_path, &song) {
+                sqlx::query(include_str!("../../queries/mark_change_undone.sql"))
+                    .bind(entry.change_id)
+                    .execute(&mut *tx)
+                    .await?;
+                tx.commit().await?;
+                return Err(sqlx::Error::InvalidArgument(format!(
+                    "Cannot undo removing {}, its file has been deleted",
+                    song.title
+                )));
+            }
+        }
+
+        // Entries come newest first, so songs touched twice end at their oldest snapshot
+        for entry in &entries {
+            match entry.before {
+                Some(ref json) => {
+                    let mut song: Song =
+                        serde_json::from_str(json).map_err(|e| sqlx::Error::Decode(Box::new(e)))?;
+                    // Plays aren't audited, so only a play count edit should change them
+                    if entry.action != "update_play_count" {
+                        if let Some(current) = snapshot(&mut tx, &song.id).await? {
+                            song.play_count = current.play_count;
+                        }
+                    }
+                    write_snapshot(&mut tx, &song).await?;
+                }
+                None => {
+                    sqlx::query(include_str!("../../queries/remove_song.sql"))
+                        .bind(&entry.song_id)
+                        .execute(&mut *tx)
+                        .await?;
+                }
+            }
+        }
+
+        if let Some(entry) = entries.first() {
+            sqlx::query(include_str!("../../queries/mark_change_undone.sql"))
+                .bind(entry.change_id)
+                .execute(&mut *tx)
+                .await?;
+        }
+        tx.commit().await?;
+
+        if !entries.is_empty() {
+            self.trigger_rekordbox_export().await;
+        }
+        Ok(entries)
+    }
+}
@@ -0,0 +1,45 @@
+use super::entities::DownloadRecord;
+use super::Database;
+
+impl Database {
+    /// Inserts or updates the persisted copy of a download job.
+    pub async fn save_download(&self, record: &DownloadRecord) -> Result<(), sqlx::Error> {
+        sqlx::query(include_str!("../../queries/save_download.sql"))
+            .bind(&record.id)
+            .bind(&record.url)
+            .bind(&record.title)
+            .bind(&record.status)
+            .bind(record.progress)
+            .bind(&record.detailed_status)
+            .bind(&record.metadata)
+            .bind(record.attempts)
+            .bind(&record.error)
+            .bind(&record.transitions)
+            .bind(record.priority)
+            .bind(&record.profile)
+            .bind(&record.logs)
+            .bind(&record.output)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    pub async fn remove_downloads(&self, ids: &[String]) -> Result<(), sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+        for id in ids {
+            sqlx::query(include_str!("../../queries/remove_download.sql"))
+                .bind(id)
+                .execute(&mut *tx)
+                .await?;
+        }
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// Every persisted job, oldest first.
+    pub async fn get_downloads(&self) -> Result<Vec<DownloadRecord>, sqlx::Error> {
+        sqlx::query_as::<_, DownloadRecord>(include_str!("../../queries/get_downloads.sql"))
+            .fetch_all(&self.pool)
+            .await
+    }
+}
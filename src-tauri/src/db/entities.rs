@@ -1,6 +1,6 @@
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Serialize, Deserialize, sqlx::FromRow)]
+#[derive(Debug, Default, Serialize, Deserialize, sqlx::FromRow)]
 pub struct Song {
     pub id: String,
     pub title: String,
@@ -9,4 +9,150 @@ pub struct Song {
     pub filename: String,
     pub source_url: Option<String>,
     pub tags: Option<String>,
+    pub rating: u8, // 0-5 stars
+    pub colour: Option<String>,
+    pub comments: Option<String>,
+    pub play_count: i64,
+    pub deleted_at: Option<i64>, // unix timestamp, set while in the trash
+    pub content_hash: Option<String>, // SHA-256 of the file, hex encoded
+    pub file_mtime: Option<i64>,
+    pub duration: Option<f64>,   // seconds
+    pub created_at: Option<i64>, // unix timestamp the song entered the library
+    pub updated_at: Option<i64>,
+    pub downloaded_at: Option<i64>, // None for songs imported from local files
+    pub version: Option<String>,    // e.g. "Extended Mix" or "Someone Remix"
+    pub remixer: Option<String>,
+    pub featured_artists: Option<String>, // comma separated, like tags
+    pub clip_start: Option<f64>,          // seconds into the source, None for the whole video
+    pub clip_end: Option<f64>,
+    pub track_number: Option<i64>, // position on the album or mix it was split from
+    pub quality_profile: Option<String>, // name of the profile it was downloaded with
+}
+
+impl Song {
+    /// Artist with its featured credits, e.g. "Artist feat. Guest".
+    pub fn full_artist(&self) -> String {
+        let featured: Vec<&str> = self
+            .featured_artists
+            .as_deref()
+            .unwrap_or_default()
+            .split(',')
+            .map(str::trim)
+            .filter(|a| !a.is_empty())
+            .collect();
+
+        if featured.is_empty() {
+            self.artist.clone()
+        } else {
+            format!("{} feat. {}", self.artist, featured.join(", "))
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, sqlx::FromRow)]
+pub struct AuditEntry {
+    pub id: i64,
+    pub change_id: i64, // shared by every row written in the same mutation
+    pub song_id: String,
+    pub action: String,
+    pub actor: Option<String>,
+    pub before: Option<String>, // JSON snapshot of the song, None if it did not exist
+    pub after: Option<String>,  // JSON snapshot of the song, None if it was removed
+    pub created_at: i64,
+    pub undone: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct DownloadRecord {
+    pub id: String,
+    pub url: String,
+    pub title: String,
+    pub status: String,
+    pub progress: f64,
+    pub detailed_status: Option<String>,
+    pub metadata: String, // JSON encoded MetadataPayload
+    pub attempts: i64,
+    pub error: Option<String>,
+    pub created_at: i64,
+    pub updated_at: i64,
+    pub transitions: Option<String>, // JSON encoded list of status changes
+    pub priority: i64,
+    pub profile: Option<String>,
+    pub logs: Option<String>, // JSON encoded list of [retry] log lines
+    pub output: Option<String>,
+}
+
+#[derive(Debug, Default, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SongSort {
+    #[default]
+    Title,
+    Artist,
+    Album,
+    Rating,
+    PlayCount,
+    Duration,
+    CreatedAt,
+    UpdatedAt,
+}
+
+impl SongSort {
+    pub fn column(&self) -> &'static str {
+        match self {
+            SongSort::Title => "title COLLATE NOCASE",
+            SongSort::Artist => "artist COLLATE NOCASE",
+            SongSort::Album => "album COLLATE NOCASE",
+            SongSort::Rating => "rating",
+            SongSort::PlayCount => "play_count",
+            SongSort::Duration => "duration",
+            SongSort::CreatedAt => "created_at",
+            SongSort::UpdatedAt => "updated_at",
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SortDirection {
+    #[default]
+    Asc,
+    Desc,
+}
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SongFilter {
+    pub search: Option<String>, // matched against title, artist and album
+    pub tag: Option<String>,
+    pub min_rating: u8,
+    pub colour: Option<String>,
+    pub added_after: Option<i64>, // unix timestamp, e.g. for a "new this week" crate
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SongQuery {
+    pub sort: SongSort,
+    pub direction: SortDirection,
+    pub offset: i64,
+    pub limit: i64,
+    pub filter: SongFilter,
+}
+
+impl Default for SongQuery {
+    fn default() -> Self {
+        SongQuery {
+            sort: SongSort::default(),
+            direction: SortDirection::default(),
+            offset: 0,
+            limit: 100,
+            filter: SongFilter::default(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SongPage {
+    pub songs: Vec<Song>,
+    pub total: i64, // matching songs across all pages
 }
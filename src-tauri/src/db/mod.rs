@@ -1,9 +1,15 @@
+use sqlx::migrate::Migrator;
 use sqlx::sqlite::{SqliteConnectOptions, SqlitePool};
 use std::str::FromStr;
 
+pub mod audit;
+pub mod downloads;
 pub mod entities;
 
-use entities::Song;
+use entities::{Song, SongPage, SongQuery, SortDirection};
+
+/// Upper bound on `SongQuery::limit`, so one IPC payload stays small.
+const MAX_PAGE_SIZE: i64 = 1000;
 
 #[derive(Clone)]
 pub struct Database {
@@ -13,17 +19,45 @@ pub struct Database {
 
 pub type DbState = std::sync::Mutex<Option<Database>>;
 
+/// Escapes `%`, `_` and `\` so user input only matches literally in a
+/// `LIKE ... ESCAPE '\'` pattern.
+fn escape_like(s: &str) -> String {
+    s.replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_")
+}
+
+pub static MIGRATOR: Migrator = sqlx::migrate!("./migrations");
+
+/// Latest migration version this build knows about.
+pub fn schema_version() -> i64 {
+    MIGRATOR.iter().map(|m| m.version).max().unwrap_or_default()
+}
+
 pub async fn init_db(db_path: &str) -> anyhow::Result<SqlitePool> {
     let opts = SqliteConnectOptions::from_str(db_path)?.create_if_missing(true);
     let pool = SqlitePool::connect_with(opts).await?;
 
-    sqlx::migrate!("./migrations").run(&pool).await?;
+    MIGRATOR.run(&pool).await?;
 
     Ok(pool)
 }
 
 impl Database {
+    /// Opens (creating if needed) the `songs.db` of the library at `library_path`.
+    pub async fn open(library_path: &str) -> anyhow::Result<Database> {
+        let pool = init_db(&format!("sqlite:{}/songs.db", library_path)).await?;
+        Ok(Database {
+            pool,
+            library_path: library_path.to_string(),
+        })
+    }
+
     pub async fn add_song(&self, song: &Song) -> Result<(), sqlx::Error> {
+        let mut tx = self.pool.begin_with(audit::BEGIN_WRITE).await?;
+        let change_id = audit::next_change_id(&mut tx).await?;
+        let before = audit::snapshot(&mut tx, &song.id).await?;
+
         sqlx::query(include_str!("../../queries/add_song.sql"))
             .bind(&song.id)
             .bind(&song.title)
@@ -32,23 +66,99 @@ impl Database {
             .bind(&song.filename)
             .bind(&song.source_url)
             .bind(&song.tags)
-            .execute(&self.pool)
+            .bind(song.rating)
+            .bind(&song.colour)
+            .bind(&song.comments)
+            .bind(song.play_count)
+            .bind(&song.content_hash)
+            .bind(song.file_mtime)
+            .bind(song.duration)
+            .bind(song.created_at)
+            .bind(song.downloaded_at)
+            .bind(&song.version)
+            .bind(&song.remixer)
+            .bind(&song.featured_artists)
+            .bind(song.clip_start)
+            .bind(song.clip_end)
+            .bind(song.track_number)
+            .bind(&song.quality_profile)
+            .execute(&mut *tx)
             .await?;
 
+        audit::record(&mut tx, change_id, "add", &song.id, before.as_ref()).await?;
+        tx.commit().await?;
+
         self.trigger_rekordbox_export().await;
         Ok(())
     }
 
-    pub async fn remove_song(&self, id: &str) -> Result<(), sqlx::Error> {
-        sqlx::query(include_str!("../../queries/remove_song.sql"))
+    pub async fn remove_songs(&self, ids: &[String]) -> Result<(), sqlx::Error> {
+        let mut tx = self.pool.begin_with(audit::BEGIN_WRITE).await?;
+        let change_id = audit::next_change_id(&mut tx).await?;
+        for id in ids {
+            let before = audit::snapshot(&mut tx, id).await?;
+            sqlx::query(include_str!("../../queries/remove_song.sql"))
+                .bind(id)
+                .execute(&mut *tx)
+                .await?;
+            audit::record(&mut tx, change_id, "remove", id, before.as_ref()).await?;
+        }
+        tx.commit().await?;
+
+        self.trigger_rekordbox_export().await;
+        Ok(())
+    }
+
+    pub async fn trash_song(&self, id: &str) -> Result<(), sqlx::Error> {
+        let mut tx = self.pool.begin_with(audit::BEGIN_WRITE).await?;
+        let change_id = audit::next_change_id(&mut tx).await?;
+        let before = audit::snapshot(&mut tx, id).await?;
+
+        sqlx::query(include_str!("../../queries/trash_song.sql"))
             .bind(id)
-            .execute(&self.pool)
+            .execute(&mut *tx)
+            .await?;
+
+        audit::record(&mut tx, change_id, "trash", id, before.as_ref()).await?;
+        tx.commit().await?;
+
+        self.trigger_rekordbox_export().await;
+        Ok(())
+    }
+
+    pub async fn restore_song(&self, id: &str) -> Result<(), sqlx::Error> {
+        let mut tx = self.pool.begin_with(audit::BEGIN_WRITE).await?;
+        let change_id = audit::next_change_id(&mut tx).await?;
+        let before = audit::snapshot(&mut tx, id).await?;
+
+        sqlx::query(include_str!("../../queries/restore_song.sql"))
+            .bind(id)
+            .execute(&mut *tx)
             .await?;
 
+        audit::record(&mut tx, change_id, "restore", id, before.as_ref()).await?;
+        tx.commit().await?;
+
         self.trigger_rekordbox_export().await;
         Ok(())
     }
 
+    pub async fn get_trashed_songs(&self) -> Result<Vec<Song>, sqlx::Error> {
+        let songs = sqlx::query_as::<_, Song>(include_str!("../../queries/get_trashed_songs.sql"))
+            .fetch_all(&self.pool)
+            .await?;
+        Ok(songs)
+    }
+
+    pub async fn get_expired_trash(&self, retention_days: u32) -> Result<Vec<Song>, sqlx::Error> {
+        let retention_secs = retention_days as i64 * 24 * 60 * 60;
+        let songs = sqlx::query_as::<_, Song>(include_str!("../../queries/get_expired_trash.sql"))
+            .bind(retention_secs)
+            .fetch_all(&self.pool)
+            .await?;
+        Ok(songs)
+    }
+
     pub async fn get_song_by_id(&self, id: &str) -> Result<Option<Song>, sqlx::Error> {
         let song = sqlx::query_as::<_, Song>(include_str!("../../queries/get_song_by_id.sql"))
             .bind(id)
@@ -57,8 +167,21 @@ impl Database {
         Ok(song)
     }
 
+    pub async fn get_song_by_filename(&self, filename: &str) -> Result<Option<Song>, sqlx::Error> {
+        let song =
+            sqlx::query_as::<_, Song>(include_str!("../../queries/get_song_by_filename.sql"))
+                .bind(filename)
+                .fetch_optional(&self.pool)
+                .await?;
+        Ok(song)
+    }
+
     #[allow(dead_code)]
     pub async fn edit_song(&self, song: &Song) -> Result<(), sqlx::Error> {
+        let mut tx = self.pool.begin_with(audit::BEGIN_WRITE).await?;
+        let change_id = audit::next_change_id(&mut tx).await?;
+        let before = audit::snapshot(&mut tx, &song.id).await?;
+
         sqlx::query(include_str!("../../queries/edit_song.sql"))
             .bind(&song.title)
             .bind(&song.artist)
@@ -66,25 +189,210 @@ impl Database {
             .bind(&song.filename)
             .bind(&song.source_url)
             .bind(&song.tags)
+            .bind(song.rating)
+            .bind(&song.colour)
+            .bind(&song.comments)
+            .bind(song.play_count)
+            .bind(&song.content_hash)
+            .bind(song.file_mtime)
+            .bind(song.duration)
+            .bind(&song.version)
+            .bind(&song.remixer)
+            .bind(&song.featured_artists)
+            .bind(song.clip_start)
+            .bind(song.clip_end)
+            .bind(song.track_number)
+            .bind(&song.quality_profile)
             .bind(&song.id)
-            .execute(&self.pool)
+            .execute(&mut *tx)
             .await?;
 
+        audit::record(&mut tx, change_id, "edit", &song.id, before.as_ref()).await?;
+        tx.commit().await?;
+
         self.trigger_rekordbox_export().await;
         Ok(())
     }
 
     pub async fn update_song_tags(&self, id: &str, tags: &str) -> Result<(), sqlx::Error> {
+        let mut tx = self.pool.begin_with(audit::BEGIN_WRITE).await?;
+        let change_id = audit::next_change_id(&mut tx).await?;
+        let before = audit::snapshot(&mut tx, id).await?;
+
         sqlx::query(include_str!("../../queries/update_song_tags.sql"))
             .bind(tags)
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+
+        audit::record(&mut tx, change_id, "update_tags", id, before.as_ref()).await?;
+        tx.commit().await?;
+
+        self.trigger_rekordbox_export().await;
+        Ok(())
+    }
+
+    pub async fn update_song_rating(&self, ids: &[String], rating: u8) -> Result<(), sqlx::Error> {
+        let mut tx = self.pool.begin_with(audit::BEGIN_WRITE).await?;
+        let change_id = audit::next_change_id(&mut tx).await?;
+        for id in ids {
+            let before = audit::snapshot(&mut tx, id).await?;
+            sqlx::query(include_str!("../../queries/update_song_rating.sql"))
+                .bind(rating)
+                .bind(id)
+                .execute(&mut *tx)
+                .await?;
+            audit::record(&mut tx, change_id, "update_rating", id, before.as_ref()).await?;
+        }
+        tx.commit().await?;
+
+        self.trigger_rekordbox_export().await;
+        Ok(())
+    }
+
+    pub async fn update_song_colour(
+        &self,
+        ids: &[String],
+        colour: Option<&str>,
+    ) -> Result<(), sqlx::Error> {
+        let mut tx = self.pool.begin_with(audit::BEGIN_WRITE).await?;
+        let change_id = audit::next_change_id(&mut tx).await?;
+        for id in ids {
+            let before = audit::snapshot(&mut tx, id).await?;
+            sqlx::query(include_str!("../../queries/update_song_colour.sql"))
+                .bind(colour)
+                .bind(id)
+                .execute(&mut *tx)
+                .await?;
+            audit::record(&mut tx, change_id, "update_colour", id, before.as_ref()).await?;
+        }
+        tx.commit().await?;
+
+        self.trigger_rekordbox_export().await;
+        Ok(())
+    }
+
+    pub async fn update_song_comments(
+        &self,
+        ids: &[String],
+        comments: Option<&str>,
+    ) -> Result<(), sqlx::Error> {
+        let mut tx = self.pool.begin_with(audit::BEGIN_WRITE).await?;
+        let change_id = audit::next_change_id(&mut tx).await?;
+        for id in ids {
+            let before = audit::snapshot(&mut tx, id).await?;
+            sqlx::query(include_str!("../../queries/update_song_comments.sql"))
+                .bind(comments)
+                .bind(id)
+                .execute(&mut *tx)
+                .await?;
+            audit::record(&mut tx, change_id, "update_comments", id, before.as_ref()).await?;
+        }
+        tx.commit().await?;
+
+        self.trigger_rekordbox_export().await;
+        Ok(())
+    }
+
+    pub async fn update_song_credits(
+        &self,
+        id: &str,
+        version: Option<&str>,
+        remixer: Option<&str>,
+        featured_artists: Option<&str>,
+    ) -> Result<(), sqlx::Error> {
+        let mut tx = self.pool.begin_with(audit::BEGIN_WRITE).await?;
+        let change_id = audit::next_change_id(&mut tx).await?;
+        let before = audit::snapshot(&mut tx, id).await?;
+
+        sqlx::query(include_str!("../../queries/update_song_credits.sql"))
+            .bind(version)
+            .bind(remixer)
+            .bind(featured_artists)
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+
+        audit::record(&mut tx, change_id, "update_credits", id, before.as_ref()).await?;
+        tx.commit().await?;
+
+        self.trigger_rekordbox_export().await;
+        Ok(())
+    }
+
+    pub async fn update_song_play_count(
+        &self,
+        ids: &[String],
+        play_count: i64,
+    ) -> Result<(), sqlx::Error> {
+        let mut tx = self.pool.begin_with(audit::BEGIN_WRITE).await?;
+        let change_id = audit::next_change_id(&mut tx).await?;
+        for id in ids {
+            let before = audit::snapshot(&mut tx, id).await?;
+            sqlx::query(include_str!("../../queries/update_song_play_count.sql"))
+                .bind(play_count)
+                .bind(id)
+                .execute(&mut *tx)
+                .await?;
+            audit::record(&mut tx, change_id, "update_play_count", id, before.as_ref()).await?;
+        }
+        tx.commit().await?;
+
+        self.trigger_rekordbox_export().await;
+        Ok(())
+    }
+
+    /// Counts a play. Not audited, so undo reverts the last edit rather than the last play.
+    pub async fn increment_play_count(&self, id: &str) -> Result<(), sqlx::Error> {
+        sqlx::query(include_str!("../../queries/increment_play_count.sql"))
             .bind(id)
             .execute(&self.pool)
             .await?;
+        // Not exported on every play, the count goes out with the next library change
+        Ok(())
+    }
+
+    pub async fn update_song_filename(&self, id: &str, filename: &str) -> Result<(), sqlx::Error> {
+        let mut tx = self.pool.begin_with(audit::BEGIN_WRITE).await?;
+        let change_id = audit::next_change_id(&mut tx).await?;
+        let before = audit::snapshot(&mut tx, id).await?;
+
+        sqlx::query(include_str!("../../queries/update_song_filename.sql"))
+            .bind(filename)
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+
+        audit::record(&mut tx, change_id, "update_filename", id, before.as_ref()).await?;
+        tx.commit().await?;
 
         self.trigger_rekordbox_export().await;
         Ok(())
     }
 
+    /// Stores the file's current hash. Not audited, it tracks the file rather than an edit.
+    pub async fn update_song_hash(
+        &self,
+        id: &str,
+        hash: &str,
+        mtime: Option<i64>,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query(include_str!("../../queries/update_song_hash.sql"))
+            .bind(hash)
+            .bind(mtime)
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    pub async fn get_unhashed_songs(&self) -> Result<Vec<Song>, sqlx::Error> {
+        let songs = sqlx::query_as::<_, Song>(include_str!("../../queries/get_unhashed_songs.sql"))
+            .fetch_all(&self.pool)
+            .await?;
+        Ok(songs)
+    }
+
     pub async fn get_songs(&self) -> Result<Vec<Song>, sqlx::Error> {
         let songs = sqlx::query_as::<_, Song>(include_str!("../../queries/get_songs.sql"))
             .fetch_all(&self.pool)
@@ -92,8 +400,57 @@ impl Database {
         Ok(songs)
     }
 
+    /// Returns one sorted, filtered page of the library together with the
+    /// number of songs matching the filter.
+    pub async fn get_songs_page(&self, query: &SongQuery) -> Result<SongPage, sqlx::Error> {
+        let filter = &query.filter;
+        let search = filter
+            .search
+            .as_ref()
+            .map(|s| format!("%{}%", escape_like(s)));
+        let tag = filter
+            .tag
+            .as_ref()
+            .map(|t| format!("%,{},%", escape_like(&t.replace(' ', ""))));
+        // Colours are stored under their canonical label, so "red" finds "Red"
+        let colour = filter
+            .colour
+            .as_deref()
+            .map(|c| crate::rekordbox::colour_label(c).unwrap_or(c));
+
+        let direction = match query.direction {
+            SortDirection::Asc => "ASC",
+            SortDirection::Desc => "DESC",
+        };
+        // Sort columns come from a fixed enum, and id breaks ties so pages never overlap
+        let order_by = format!("{} {}, id ASC", query.sort.column(), direction);
+        let sql = include_str!("../../queries/get_songs_page.sql").replace("{order_by}", &order_by);
+
+        let songs = sqlx::query_as::<_, Song>(sqlx::AssertSqlSafe(sql))
+            .bind(&search)
+            .bind(&tag)
+            .bind(filter.min_rating)
+            .bind(colour)
+            .bind(filter.added_after)
+            .bind(query.limit.clamp(0, MAX_PAGE_SIZE))
+            .bind(query.offset.max(0))
+            .fetch_all(&self.pool)
+            .await?;
+
+        let total: i64 = sqlx::query_scalar(include_str!("../../queries/count_songs.sql"))
+            .bind(&search)
+            .bind(&tag)
+            .bind(filter.min_rating)
+            .bind(colour)
+            .bind(filter.added_after)
+            .fetch_one(&self.pool)
+            .await?;
+
+        Ok(SongPage { songs, total })
+    }
+
     pub async fn search_songs(&self, query: &str) -> Result<Vec<Song>, sqlx::Error> {
-        let search_term = format!("%{}%", query);
+        let search_term = format!("%{}%", escape_like(query));
         let songs = sqlx::query_as::<_, Song>(include_str!("../../queries/search_songs.sql"))
             .bind(&search_term)
             .fetch_all(&self.pool)
@@ -101,7 +458,7 @@ impl Database {
         Ok(songs)
     }
 
-    async fn trigger_rekordbox_export(&self) {
+    pub async fn trigger_rekordbox_export(&self) {
         let songs = match self.get_songs().await {
             Ok(s) => s,
             Err(e) => {
@@ -122,7 +479,7 @@ mod tests {
 
     async fn setup_test_db(suffix: &str) -> Database {
         let pool = SqlitePool::connect("sqlite::memory:").await.unwrap();
-        sqlx::migrate!("./migrations").run(&pool).await.unwrap();
+        MIGRATOR.run(&pool).await.unwrap();
         let mut temp_dir = std::env::temp_dir();
         temp_dir.push(format!("cue_test_{}_{}", std::process::id(), suffix));
         std::fs::create_dir_all(&temp_dir).unwrap();
@@ -143,6 +500,7 @@ mod tests {
             filename: "test.mp3".to_string(),
             source_url: Some("https://example.com".to_string()),
             tags: None,
+            ..Default::default()
         };
 
         db.add_song(&song).await.unwrap();
@@ -163,6 +521,7 @@ mod tests {
             filename: "path1".to_string(),
             source_url: None,
             tags: None,
+            ..Default::default()
         };
         let song2 = Song {
             id: "2".to_string(),
@@ -172,6 +531,7 @@ mod tests {
             filename: "path2".to_string(),
             source_url: None,
             tags: None,
+            ..Default::default()
         };
 
         db.add_song(&song1).await.unwrap();
@@ -182,6 +542,92 @@ mod tests {
         assert_eq!(results[0].title, "Apple");
     }
 
+    #[tokio::test]
+    async fn test_get_songs_page() {
+        let db = setup_test_db("page").await;
+        for (i, (title, rating, tags)) in [
+            ("Cherry", 5, "house,banger"),
+            ("apple", 3, "techno"),
+            ("Banana", 4, "house"),
+            ("Date", 1, "house"),
+        ]
+        .into_iter()
+        .enumerate()
+        {
+            let song = Song {
+                id: i.to_string(),
+                title: title.to_string(),
+                artist: "Artist".to_string(),
+                filename: format!("{}.mp3", title),
+                tags: Some(tags.to_string()),
+                rating,
+                ..Default::default()
+            };
+            db.add_song(&song).await.unwrap();
+        }
+
+        let page = db
+            .get_songs_page(&SongQuery {
+                limit: 2,
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+        assert_eq!(page.total, 4);
+        let titles: Vec<_> = page.songs.iter().map(|s| s.title.as_str()).collect();
+        assert_eq!(titles, ["apple", "Banana"]);
+
+        let page = db
+            .get_songs_page(&SongQuery {
+                sort: entities::SongSort::Rating,
+                direction: SortDirection::Desc,
+                offset: 1,
+                filter: entities::SongFilter {
+                    tag: Some("house".to_string()),
+                    min_rating: 2,
+                    ..Default::default()
+                },
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+        assert_eq!(page.total, 2);
+        assert_eq!(page.songs.len(), 1);
+        assert_eq!(page.songs[0].title, "Banana");
+
+        db.update_song_colour(&["1".to_string()], Some("Red"))
+            .await
+            .unwrap();
+        let page = db
+            .get_songs_page(&SongQuery {
+                filter: entities::SongFilter {
+                    colour: Some("red".to_string()),
+                    ..Default::default()
+                },
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+        assert_eq!(page.total, 1);
+        assert_eq!(page.songs[0].title, "apple");
+
+        // Wildcards in the search only match themselves
+        for search in ["%", "_"] {
+            let page = db
+                .get_songs_page(&SongQuery {
+                    filter: entities::SongFilter {
+                        search: Some(search.to_string()),
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                })
+                .await
+                .unwrap();
+            assert_eq!(page.total, 0);
+        }
+        assert!(db.search_songs("_").await.unwrap().is_empty());
+    }
+
     #[tokio::test]
     async fn test_export_rekordbox_xml() {
         let db = setup_test_db("export").await;
@@ -193,6 +639,8 @@ mod tests {
             filename: "test.mp3".to_string(),
             source_url: None,
             tags: None,
+            created_at: Some(1_700_000_000),
+            ..Default::default()
         };
 
         // Ensure Songs directory exists
@@ -214,5 +662,319 @@ mod tests {
         assert!(xml_content.contains("Artist=\"Test Artist\""));
         assert!(xml_content.contains("Location=\"file://localhost"));
         assert!(xml_content.contains("test.mp3\""));
+        assert!(xml_content.contains("Kind=\"MP3 File\""));
+        assert!(xml_content.contains("DateAdded=\"2023-11-14\""));
+
+        db.update_song_credits("1", Some("Extended Mix"), None, Some("Guest One,Guest Two"))
+            .await
+            .unwrap();
+        let xml_content = std::fs::read_to_string(library_path.join("rekordbox.xml")).unwrap();
+        assert!(xml_content.contains("Artist=\"Test Artist feat. Guest One, Guest Two\""));
+        assert!(xml_content.contains("Mix=\"Extended Mix\""));
+        assert!(!xml_content.contains("Remixer="));
+
+        db.update_song_credits("1", Some("Someone Remix"), Some("Someone"), None)
+            .await
+            .unwrap();
+        let xml_content = std::fs::read_to_string(library_path.join("rekordbox.xml")).unwrap();
+        assert!(xml_content.contains("Artist=\"Test Artist\""));
+        assert!(xml_content.contains("Mix=\"Someone Remix\""));
+        assert!(xml_content.contains("Remixer=\"Someone\""));
+    }
+
+    #[tokio::test]
+    async fn test_song_timestamps() {
+        let db = setup_test_db("timestamps").await;
+        let song = Song {
+            id: "1".to_string(),
+            title: "Song".to_string(),
+            artist: "Artist".to_string(),
+            filename: "song.mp3".to_string(),
+            created_at: Some(1_000),
+            downloaded_at: Some(2_000),
+            clip_start: Some(93.5),
+            clip_end: Some(420.0),
+            track_number: Some(3),
+            ..Default::default()
+        };
+        db.add_song(&song).await.unwrap();
+
+        let stored = db.get_song_by_id("1").await.unwrap().unwrap();
+        assert_eq!(stored.clip_start, Some(93.5));
+        assert_eq!(stored.clip_end, Some(420.0));
+        assert_eq!(stored.track_number, Some(3));
+        assert_eq!(stored.created_at, Some(1_000));
+        assert_eq!(stored.downloaded_at, Some(2_000));
+        assert!(stored.updated_at.unwrap() > 1_000);
+
+        // A re-download keeps the original date added
+        let redownload = Song {
+            created_at: None,
+            downloaded_at: Some(3_000),
+            ..song
+        };
+        db.add_song(&redownload).await.unwrap();
+        let stored = db.get_song_by_id("1").await.unwrap().unwrap();
+        assert_eq!(stored.created_at, Some(1_000));
+        assert_eq!(stored.downloaded_at, Some(3_000));
+
+        let page = db
+            .get_songs_page(&SongQuery {
+                filter: entities::SongFilter {
+                    added_after: Some(500),
+                    ..Default::default()
+                },
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+        assert_eq!(page.total, 1);
+    }
+
+    #[tokio::test]
+    async fn test_redownload_keeps_user_fields() {
+        let db = setup_test_db("redownload").await;
+        let song = Song {
+            id: "1".to_string(),
+            title: "Song".to_string(),
+            artist: "Artist".to_string(),
+            filename: "song.mp3".to_string(),
+            ..Default::default()
+        };
+        db.add_song(&song).await.unwrap();
+
+        let ids = vec!["1".to_string()];
+        db.update_song_rating(&ids, 4).await.unwrap();
+        db.update_song_colour(&ids, Some("Red")).await.unwrap();
+        db.update_song_comments(&ids, Some("Peak time"))
+            .await
+            .unwrap();
+        db.update_song_tags("1", "techno").await.unwrap();
+        db.increment_play_count("1").await.unwrap();
+
+        // A re-download only knows what the source says about the track
+        let redownload = Song {
+            title: "Song (Remastered)".to_string(),
+            filename: "song.flac".to_string(),
+            ..song
+        };
+        db.add_song(&redownload).await.unwrap();
+
+        let stored = db.get_song_by_id("1").await.unwrap().unwrap();
+        assert_eq!(stored.title, "Song (Remastered)");
+        assert_eq!(stored.filename, "song.flac");
+        assert_eq!(stored.rating, 4);
+        assert_eq!(stored.colour.as_deref(), Some("Red"));
+        assert_eq!(stored.comments.as_deref(), Some("Peak time"));
+        assert_eq!(stored.tags.as_deref(), Some("techno"));
+        assert_eq!(stored.play_count, 1);
+    }
+
+    #[tokio::test]
+    async fn test_update_song_attributes() {
+        let db = setup_test_db("attributes").await;
+        for id in ["1", "2"] {
+            let song = Song {
+                id: id.to_string(),
+                title: format!("Song {}", id),
+                artist: "Artist".to_string(),
+                filename: format!("{}.mp3", id),
+                ..Default::default()
+            };
+            db.add_song(&song).await.unwrap();
+        }
+
+        // Plays aren't exported on their own, they go out with the next edit
+        db.increment_play_count("1").await.unwrap();
+        db.increment_play_count("1").await.unwrap();
+        let ids = vec!["1".to_string(), "2".to_string()];
+        db.update_song_rating(&ids, 4).await.unwrap();
+        db.update_song_colour(&ids, Some("Red")).await.unwrap();
+        db.update_song_comments(&ids[..1], Some("Peak time"))
+            .await
+            .unwrap();
+
+        let song = db.get_song_by_id("1").await.unwrap().unwrap();
+        assert_eq!(song.rating, 4);
+        assert_eq!(song.colour.as_deref(), Some("Red"));
+        assert_eq!(song.comments.as_deref(), Some("Peak time"));
+        assert_eq!(song.play_count, 2);
+
+        let other = db.get_song_by_id("2").await.unwrap().unwrap();
+        assert_eq!(other.rating, 4);
+        assert_eq!(other.comments, None);
+        assert_eq!(other.play_count, 0);
+
+        let xml_content =
+            std::fs::read_to_string(std::path::Path::new(&db.library_path).join("rekordbox.xml"))
+                .unwrap();
+        assert!(xml_content.contains("Rating=\"204\""));
+        assert!(xml_content.contains("Colour=\"0xFF0000\""));
+        assert!(xml_content.contains("Comments=\"Peak time\""));
+        assert!(xml_content.contains("PlayCount=\"2\""));
+    }
+
+    #[tokio::test]
+    async fn test_trash_and_restore_song() {
+        let db = setup_test_db("trash").await;
+        let song = Song {
+            id: "1".to_string(),
+            title: "Trashed Song".to_string(),
+            artist: "Test Artist".to_string(),
+            filename: "trashed.mp3".to_string(),
+            ..Default::default()
+        };
+        db.add_song(&song).await.unwrap();
+
+        db.trash_song("1").await.unwrap();
+        assert!(db.get_songs().await.unwrap().is_empty());
+        assert!(db.search_songs("Trashed").await.unwrap().is_empty());
+
+        let trashed = db.get_trashed_songs().await.unwrap();
+        assert_eq!(trashed.len(), 1);
+        assert!(trashed[0].deleted_at.is_some());
+
+        let xml_path = std::path::Path::new(&db.library_path).join("rekordbox.xml");
+        let xml_content = std::fs::read_to_string(&xml_path).unwrap();
+        assert!(!xml_content.contains("TrackID=\"1\""));
+
+        // Just trashed, so nothing has expired yet
+        assert!(db.get_expired_trash(30).await.unwrap().is_empty());
+        assert_eq!(db.get_expired_trash(0).await.unwrap().len(), 1);
+
+        db.restore_song("1").await.unwrap();
+        assert_eq!(db.get_songs().await.unwrap().len(), 1);
+        assert!(db.get_trashed_songs().await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_history_and_undo() {
+        let db = setup_test_db("undo").await;
+        for id in ["1", "2"] {
+            let song = Song {
+                id: id.to_string(),
+                title: format!("Song {}", id),
+                artist: "Artist".to_string(),
+                filename: format!("{}.mp3", id),
+                rating: 2,
+                ..Default::default()
+            };
+            db.add_song(&song).await.unwrap();
+        }
+
+        let ids = vec!["1".to_string(), "2".to_string()];
+        db.update_song_rating(&ids, 5).await.unwrap();
+        db.increment_play_count("1").await.unwrap();
+
+        // Plays aren't edits, so they stay out of the log
+        let history = db.get_history(None, 10).await.unwrap();
+        assert_eq!(history.len(), 4);
+        assert_eq!(history[0].action, "update_rating");
+        assert_eq!(history[0].change_id, history[1].change_id);
+        assert_eq!(db.get_history(Some("1"), 10).await.unwrap().len(), 2);
+
+        // Undoing the bulk edit reverts both songs at once
+        let undone = db.undo_last_change().await.unwrap();
+        assert_eq!(undone.len(), 2);
+        for song in db.get_songs().await.unwrap() {
+            assert_eq!(song.rating, 2);
+        }
+        let played = db.get_song_by_id("1").await.unwrap().unwrap();
+        assert_eq!(played.play_count, 1);
+
+        // The next undo reverts the second add
+        db.undo_last_change().await.unwrap();
+        assert!(db.get_song_by_id("2").await.unwrap().is_none());
+        assert!(db.get_song_by_id("1").await.unwrap().is_some());
+
+        let history = db.get_history(None, 10).await.unwrap();
+        assert_eq!(history.iter().filter(|e| e.undone).count(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_undo_purged_remove() {
+        let db = setup_test_db("undo_remove").await;
+        let songs_dir = std::path::Path::new(&db.library_path).join("Songs");
+        std::fs::create_dir_all(&songs_dir).unwrap();
+        std::fs::write(songs_dir.join("kept.mp3"), b"audio").unwrap();
+
+        for id in ["kept", "purged"] {
+            let song = Song {
+                id: id.to_string(),
+                title: id.to_string(),
+                artist: "Artist".to_string(),
+                filename: format!("{}.mp3", id),
+                ..Default::default()
+            };
+            db.add_song(&song).await.unwrap();
+        }
+
+        // The file is still there, so the entry can come back
+        db.remove_songs(&["kept".to_string()]).await.unwrap();
+        db.undo_last_change().await.unwrap();
+        assert!(db.get_song_by_id("kept").await.unwrap().is_some());
+
+        db.remove_songs(&["purged".to_string()]).await.unwrap();
+        assert!(db.undo_last_change().await.is_err());
+        assert!(db.get_song_by_id("purged").await.unwrap().is_none());
+
+        // The purged remove is skipped, the change before it is next
+        let entries = db.undo_last_change().await.unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].action, "add");
+        assert_eq!(entries[0].song_id, "purged");
+    }
+
+    #[tokio::test]
+    async fn test_persist_downloads() {
+        let db = setup_test_db("downloads").await;
+        let record = |id: &str, status: &str| entities::DownloadRecord {
+            id: id.to_string(),
+            url: format!("https://example.com/{}", id),
+            title: format!("Job {}", id),
+            status: status.to_string(),
+            progress: 0.0,
+            detailed_status: None,
+            metadata: "{}".to_string(),
+            attempts: 0,
+            error: None,
+            created_at: 0,
+            updated_at: 0,
+            transitions: None,
+            priority: 0,
+            profile: None,
+            logs: None,
+            output: None,
+        };
+
+        db.save_download(&record("a", "queued")).await.unwrap();
+        db.save_download(&record("b", "queued")).await.unwrap();
+        db.save_download(&entities::DownloadRecord {
+            attempts: 1,
+            error: Some("boom".to_string()),
+            priority: 3,
+            logs: Some(r#"["[retry] Starting attempt 2 of 3"]"#.to_string()),
+            ..record("a", "error")
+        })
+        .await
+        .unwrap();
+
+        let downloads = db.get_downloads().await.unwrap();
+        assert_eq!(downloads.len(), 2);
+        // Updating a job keeps its place in the queue
+        assert_eq!(downloads[0].id, "a");
+        assert_eq!(downloads[0].status, "error");
+        assert_eq!(downloads[0].attempts, 1);
+        assert_eq!(downloads[0].error.as_deref(), Some("boom"));
+        assert_eq!(downloads[0].priority, 3);
+        assert_eq!(
+            downloads[0].logs.as_deref(),
+            Some(r#"["[retry] Starting attempt 2 of 3"]"#)
+        );
+
+        db.remove_downloads(&["a".to_string()]).await.unwrap();
+        let downloads = db.get_downloads().await.unwrap();
+        assert_eq!(downloads.len(), 1);
+        assert_eq!(downloads[0].id, "b");
     }
 }
@@ -0,0 +1,245 @@
+use serde::{Deserialize, Serialize};
+
+use super::title;
+use super::types::{clip_id, MetadataPayload};
+
+/// A track inside a longer video, from its chapters or its description.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Chapter {
+    pub title: String,
+    pub start: f64,       // seconds
+    pub end: Option<f64>, // None runs to the end of the video
+}
+
+/// Parses "m:ss" or "h:mm:ss" into seconds.
+fn parse_timestamp(text: &str) -> Option<f64> {
+    let parts: Vec<&str> = text.split(':').collect();
+    if !(2..=3).contains(&parts.len()) || parts.iter().any(|p| p.is_empty()) {
+        return None;
+    }
+    if !parts.iter().all(|p| p.chars().all(|c| c.is_ascii_digit())) {
+        return None;
+    }
+    // Everything after the leading field is a two digit minute or second
+    if parts[1..].iter().any(|p| p.len() != 2) {
+        return None;
+    }
+
+    let mut seconds = 0u64;
+    for (i, part) in parts.iter().enumerate() {
+        let value: u64 = part.parse().ok()?;
+        if i > 0 && value >= 60 {
+            return None;
+        }
+        seconds = seconds * 60 + value;
+    }
+    Some(seconds as f64)
+}
+
+/// Removes a leading track number such as "01." or "3)" from a track name.
+pub fn strip_track_number(name: &str) -> &str {
+    let name = name.trim();
+    let digits = name.len() - name.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+    if digits == 0 || digits > 3 {
+        return name;
+    }
+    let rest = &name[digits..];
+    for separator in [".", ")", " -", " –"] {
+        if let Some(stripped) = rest.strip_prefix(separator) {
+            return stripped.trim();
+        }
+    }
+    name
+}
+
+/// Reads a tracklist such as "0:00 Artist - Title" or "1. Title [12:34]"
+/// out of a video description. Returns nothing unless there are at least two
+/// timestamps in increasing order, so a single "drop at 1:23" isn't a tracklist.
+pub fn from_description(description: &str, duration: Option<f64>) -> Vec<Chapter> {
+    let mut tracks: Vec<(f64, String)> = Vec::new();
+    for line in description.lines() {
+        let words: Vec<&str> = line.split_whitespace().collect();
+        let found = words.iter().enumerate().find_map(|(i, word)| {
+            let word = word.trim_matches(|c| matches!(c, '[' | ']' | '(' | ')'));
+            let start = word.split(['-', '–']).next().unwrap_or_default();
+            parse_timestamp(start).map(|seconds| (i, seconds))
+        });
+        let Some((index, start)) = found else {
+            continue;
+        };
+
+        let rest: Vec<&str> = words
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| *i != index)
+            .map(|(_, w)| *w)
+            .collect();
+        let name = rest
+            .join(" ")
+            .trim_matches(|c: char| c.is_whitespace() || "-–—|:•".contains(c))
+            .to_string();
+        tracks.push((start, strip_track_number(&name).to_string()));
+    }
+
+    let increasing = tracks.windows(2).all(|pair| pair[0].0 < pair[1].0);
+    let within = |start: f64| duration.is_none_or(|d| start < d);
+    if tracks.len() < 2 || !increasing || !tracks.iter().all(|(s, _)| within(*s)) {
+        return Vec::new();
+    }
+
+    let ends: Vec<Option<f64>> = tracks
+        .iter()
+        .skip(1)
+        .map(|(start, _)| Some(*start))
+        .chain(std::iter::once(None))
+        .collect();
+    tracks
+        .into_iter()
+        .zip(ends)
+        .enumerate()
+        .map(|(i, ((start, name), end))| Chapter {
+            title: if name.is_empty() {
+                format!("Track {}", i + 1)
+            } else {
+                name
+            },
+            start,
+            end,
+        })
+        .collect()
+}
+
+/// One download per chapter of `metadata`, each cut to its chapter, with
+/// artist and title parsed from the chapter name, its track number, and the
+/// video's title as the album.
+pub fn split(metadata: &MetadataPayload) -> Result<Vec<MetadataPayload>, String> {
+    if metadata.chapters.is_empty() {
+        return Err("This video has no chapters or tracklist to split".into());
+    }
+
+    let mut tracks = Vec::new();
+    for (i, chapter) in metadata.chapters.iter().enumerate() {
+        // Chapters usually leave the artist out when it's the uploader's own album
+        let parsed = title::parse(
+            strip_track_number(&chapter.title),
+            None,
+            Some(&metadata.artist),
+        );
+
+        let mut track = metadata.clone();
+        track.title = parsed.title;
+        track.artist = parsed.artist;
+        track.featured_artists = Some(parsed.featured_artists.join(", ")).filter(|f| !f.is_empty());
+        track.version = parsed.version;
+        track.remixer = parsed.remixer;
+        track.album = Some(metadata.title.clone());
+        track.track_number = Some(i as i64 + 1);
+        track.chapters = Vec::new();
+        track.set_clip(Some(chapter.start), chapter.end)?;
+        track.id = clip_id(&metadata.id, track.clip_start, track.clip_end);
+        tracks.push(track);
+    }
+    Ok(tracks)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_description() {
+        let description = "Full album out now!\n\
+            Tracklist:\n\
+            1. 0:00 Intro\n\
+            2. 03:25 Dynoro - In My Mind\n\
+            [1:02:03] Someone feat. Guest - Closer (Extended Mix)\n\
+            \n\
+            Follow me on Instagram";
+        let chapters = from_description(description, Some(4000.0));
+        assert_eq!(
+            chapters,
+            [
+                Chapter {
+                    title: "Intro".into(),
+                    start: 0.0,
+                    end: Some(205.0)
+                },
+                Chapter {
+                    title: "Dynoro - In My Mind".into(),
+                    start: 205.0,
+                    end: Some(3723.0)
+                },
+                Chapter {
+                    title: "Someone feat. Guest - Closer (Extended Mix)".into(),
+                    start: 3723.0,
+                    end: None
+                },
+            ]
+        );
+
+        let trailing = from_description("Song One - 0:00\nSong Two | 4:10\n", None);
+        assert_eq!(trailing[0].title, "Song One");
+        assert_eq!(trailing[1].title, "Song Two");
+
+        // A single timestamp, times out of order or past the end aren't tracklists
+        assert!(from_description("The drop at 1:23 is insane", None).is_empty());
+        assert!(from_description("0:00 A\n5:00 B\n2:00 C", None).is_empty());
+        assert!(from_description("0:00 A\n5:00 B", Some(120.0)).is_empty());
+        assert!(from_description("Call 12:345 or 1:2", None).is_empty());
+    }
+
+    #[test]
+    fn test_strip_track_number() {
+        assert_eq!(strip_track_number("01. Intro"), "Intro");
+        assert_eq!(strip_track_number("3) Outro"), "Outro");
+        assert_eq!(strip_track_number("12 - Closer"), "Closer");
+        assert_eq!(strip_track_number("22 Acacia Avenue"), "22 Acacia Avenue");
+        assert_eq!(strip_track_number("1999"), "1999");
+    }
+
+    #[test]
+    fn test_split() {
+        let mut metadata: MetadataPayload = serde_json::from_value(serde_json::json!({
+            "id": "abc",
+            "url": "https://example.com/abc",
+            "title": "Night Drive",
+            "artist": "Dynoro",
+            "album": null,
+            "thumbnail": null,
+            "duration": 600.0,
+        }))
+        .unwrap();
+        assert!(split(&metadata).is_err());
+
+        metadata.chapters = vec![
+            Chapter {
+                title: "01. Intro".into(),
+                start: 0.0,
+                end: Some(200.0),
+            },
+            Chapter {
+                title: "Guest Artist - Second (VIP Mix)".into(),
+                start: 200.0,
+                end: Some(600.0),
+            },
+        ];
+        let tracks = split(&metadata).unwrap();
+        assert_eq!(tracks.len(), 2);
+
+        assert_eq!(tracks[0].id, "abc@0-200");
+        assert_eq!(tracks[0].title, "Intro");
+        assert_eq!(tracks[0].artist, "Dynoro");
+        assert_eq!(tracks[0].album.as_deref(), Some("Night Drive"));
+        assert_eq!(tracks[0].track_number, Some(1));
+        assert_eq!(tracks[0].clip_start, None);
+        assert_eq!(tracks[0].clip_end, Some(200.0));
+        assert_eq!(tracks[0].duration, Some(200.0));
+
+        assert_eq!(tracks[1].id, "abc@200-600");
+        assert_eq!(tracks[1].artist, "Guest Artist");
+        assert_eq!(tracks[1].title, "Second");
+        assert_eq!(tracks[1].version.as_deref(), Some("VIP Mix"));
+        assert_eq!(tracks[1].track_number, Some(2));
+        assert!(tracks[1].chapters.is_empty());
+    }
+}
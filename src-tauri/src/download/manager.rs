@@ -1,20 +1,150 @@
+use serde::Deserialize;
+use std::collections::{BTreeMap, HashMap};
+use std::hash::{BuildHasher, RandomState};
 use std::process::Stdio;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Mutex;
+use std::time::Duration;
 use tauri::{AppHandle, Emitter, Manager};
 use tokio::io::BufReader;
 use tokio::process::{Child, ChildStderr, ChildStdout, Command};
-use tokio::sync::oneshot;
+use tokio::sync::{mpsc, oneshot};
 
-use super::types::{DownloadErrorPayload, DownloadJob};
-use crate::download::process::run_download;
+use super::types::{now, DownloadErrorPayload, DownloadJob, JobStatus};
+use crate::db::{Database, DbState};
+use crate::download::process::{run_download, DownloadRequest};
+use crate::filename;
 
 struct JobState {
     job: DownloadJob,
     cancel_tx: Option<oneshot::Sender<()>>,
 }
 
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum QueueMove {
+    Top,
+    Up,
+    Down,
+}
+
+/// Indices of the jobs waiting in the queue, in the order they will start.
+fn queue_order(jobs: &[JobState]) -> Vec<usize> {
+    let mut order: Vec<usize> = (0..jobs.len())
+        .filter(|&i| matches!(jobs[i].job.status, JobStatus::Queued | JobStatus::Paused))
+        .collect();
+    // Stable, so equal priorities keep the order they were added in
+    order.sort_by_key(|&i| std::cmp::Reverse(jobs[i].job.priority));
+    order
+}
+
+/// Queue order of `ids` after moving `id`, or None when it can't move that way.
+fn moved(ids: &[String], id: &str, direction: QueueMove) -> Option<Vec<String>> {
+    let from = ids.iter().position(|i| i == id)?;
+    let to = match direction {
+        QueueMove::Top => 0,
+        QueueMove::Up => from.checked_sub(1)?,
+        QueueMove::Down if from + 1 < ids.len() => from + 1,
+        QueueMove::Down => return None,
+    };
+    let mut ids = ids.to_vec();
+    let id = ids.remove(from);
+    ids.insert(to, id);
+    Some(ids)
+}
+
+/// Writes to the `downloads` table, applied in order by a single task.
+enum PersistOp {
+    Save(Box<DownloadJob>),
+    Remove(Vec<String>),
+    /// Sends later writes to this library, acknowledged once earlier ones are done
+    Use(Database, oneshot::Sender<()>),
+}
+
+/// How failed downloads are retried.
+#[derive(Debug, Clone, Copy)]
+pub struct RetryPolicy {
+    /// Attempts per job including the first one
+    pub max_attempts: u32,
+    pub base_delay: Duration,
+    pub max_delay: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            base_delay: Duration::from_secs(5),
+            max_delay: Duration::from_secs(300),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Wait after the given failed attempt. The delay doubles with every
+    /// attempt up to `max_delay`, and `jitter` (0..1) spreads its upper half
+    /// so jobs that failed together don't retry together.
+    fn delay(&self, attempt: u32, jitter: f64) -> Duration {
+        let factor = 2u32.saturating_pow(attempt.saturating_sub(1));
+        let delay = self.base_delay.saturating_mul(factor).min(self.max_delay);
+        delay / 2 + (delay / 2).mul_f64(jitter.clamp(0.0, 1.0))
+    }
+}
+
+fn jitter() -> f64 {
+    RandomState::new().hash_one(now()) as f64 / u64::MAX as f64
+}
+
+/// Whether a failure is likely to go away on its own, like rate limiting,
+/// network errors or a flaky extractor. Unavailable or private videos are not retried.
+fn is_retryable(error: &str) -> bool {
+    let error = error.to_lowercase();
+    const PERMANENT: [&str; 14] = [
+        "video unavailable",
+        "private video",
+        "has been removed",
+        "copyright",
+        "sign in to confirm your age",
+        "members-only",
+        "http error 403",
+        "http error 404",
+        "forbidden",
+        "unsupported url",
+        "not available in your country",
+        "not made this video available in your country",
+        "geo restriction",
+        "geo-restricted",
+    ];
+    const TRANSIENT: [&str; 13] = [
+        "http error 429",
+        "too many requests",
+        "http error 5",
+        "timed out",
+        "connection reset",
+        "connection refused",
+        "remote end closed",
+        "incompleteread",
+        "temporary failure in name resolution",
+        "unable to download webpage",
+        "unable to download video data",
+        "unable to extract",
+        // yt-dlp was killed without reporting an error
+        "exit code: none",
+    ];
+    !PERMANENT.iter().any(|p| error.contains(p)) && TRANSIENT.iter().any(|t| error.contains(t))
+}
+
 pub struct DownloadManager {
     jobs: Mutex<Vec<JobState>>,
+    retry: RetryPolicy,
+    /// Stops new jobs from starting while set, running ones carry on
+    queue_paused: AtomicBool,
+    /// Set while the library is being changed, so no job starts in the old one
+    switching: AtomicBool,
+    persist_tx: mpsc::UnboundedSender<PersistOp>,
+    persist_rx: Mutex<Option<mpsc::UnboundedReceiver<PersistOp>>>,
+    /// Output names of running jobs by job id, so two downloads never write the same file
+    stems: tokio::sync::Mutex<HashMap<String, String>>,
 }
 
 type ProcessOutput = (
@@ -26,8 +156,166 @@ type ProcessOutput = (
 
 impl DownloadManager {
     pub fn new() -> Self {
+        let (persist_tx, persist_rx) = mpsc::unbounded_channel();
         Self {
             jobs: Mutex::new(Vec::new()),
+            retry: RetryPolicy::default(),
+            queue_paused: AtomicBool::new(false),
+            switching: AtomicBool::new(false),
+            persist_tx,
+            persist_rx: Mutex::new(Some(persist_rx)),
+            stems: tokio::sync::Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Starts the task that mirrors job changes into the current library's database.
+    pub fn start_persistence(&self, app: &AppHandle) {
+        let Some(mut rx) = self.persist_rx.lock().unwrap().take() else {
+            return;
+        };
+        let app = app.clone();
+        tauri::async_runtime::spawn(async move {
+            // Until a library is switched to, writes follow whichever one is open
+            let mut library: Option<Database> = None;
+            while let Some(op) = rx.recv().await {
+                let db = library.clone().or_else(|| {
+                    let state = app.state::<DbState>();
+                    let db_guard = state.lock().unwrap();
+                    db_guard.clone()
+                });
+                let Some(db) = db else {
+                    continue;
+                };
+
+                let result = match op {
+                    PersistOp::Save(job) => match job.to_record() {
+                        Ok(record) => db.save_download(&record).await,
+                        Err(e) => Err(sqlx::Error::Encode(Box::new(e))),
+                    },
+                    PersistOp::Remove(ids) => db.remove_downloads(&ids).await,
+                    PersistOp::Use(db, done) => {
+                        library = Some(db);
+                        let _ = done.send(());
+                        Ok(())
+                    }
+                };
+                if let Err(e) = result {
+                    eprintln!("Failed to persist download queue: {}", e);
+                }
+            }
+        });
+    }
+
+    /// Loads the persisted queue and history, re-queuing jobs that were
+    /// running when the app last exited.
+    pub async fn restore(&self, app: &AppHandle, db: &Database) -> anyhow::Result<()> {
+        let mut restored = Vec::new();
+        for record in db.get_downloads().await? {
+            let mut job = match DownloadJob::from_record(record) {
+                Ok(job) => job,
+                Err(e) => {
+                    eprintln!("Skipping unreadable download: {}", e);
+                    continue;
+                }
+            };
+            if job.status.is_active() {
+                let _ = job.transition(JobStatus::Queued);
+                job.progress = 0.0;
+                job.detailed_status = None;
+                let _ = self.persist_tx.send(PersistOp::Save(Box::new(job.clone())));
+            }
+            restored.push(JobState {
+                job,
+                cancel_tx: None,
+            });
+        }
+
+        {
+            let mut jobs = self.jobs.lock().unwrap();
+            // Jobs added before the restore finished stay at the end of the queue
+            let added = std::mem::take(&mut *jobs);
+            restored.retain(|r| !added.iter().any(|a| a.job.id == r.job.id));
+            *jobs = restored;
+            jobs.extend(added);
+        }
+        self.emit_update(app);
+        self.trigger_processing(app);
+        Ok(())
+    }
+
+    /// Holds the queue while the library is switched, moved or restored. Fails
+    /// while a download is running, as it would finish into the old library.
+    pub fn pause_for_switch(&self, app: &AppHandle) -> Result<LibrarySwitch<'_>, String> {
+        // Checked under the jobs lock, which the scheduler holds while starting jobs
+        let jobs = self.jobs.lock().unwrap();
+        if jobs.iter().any(|j| j.job.status.is_active()) {
+            return Err("Wait for active downloads to finish before changing libraries".into());
+        }
+        self.switching.store(true, Ordering::SeqCst);
+        Ok(LibrarySwitch {
+            manager: self,
+            app: app.clone(),
+        })
+    }
+
+    /// Replaces the queue with the one saved in `db`, after the old library
+    /// has received every pending write.
+    pub async fn load_library(&self, app: &AppHandle, db: &Database) -> anyhow::Result<()> {
+        let (done, flushed) = oneshot::channel();
+        let _ = self.persist_tx.send(PersistOp::Use(db.clone(), done));
+        let _ = flushed.await;
+
+        self.jobs.lock().unwrap().clear();
+        self.restore(app, db).await
+    }
+
+    /// Picks the output name for job `id` and holds it until `release_stem`.
+    /// The lock is held while picking, so concurrent jobs can't pick the same name.
+    pub async fn reserve_stem(
+        &self,
+        db: &Database,
+        rendered: &str,
+        id: &str,
+        exts: &[&str],
+    ) -> Result<String, sqlx::Error> {
+        let mut stems = self.stems.lock().await;
+        let reserved: Vec<&str> = stems
+            .iter()
+            .filter(|(job, _)| job.as_str() != id)
+            .map(|(_, stem)| stem.as_str())
+            .collect();
+        let stem = filename::unique_stem(db, rendered, id, exts, &reserved).await?;
+        stems.insert(id.to_string(), stem.clone());
+        self.set_output(id, &stem);
+        Ok(stem)
+    }
+
+    fn set_output(&self, id: &str, stem: &str) {
+        {
+            let mut jobs = self.jobs.lock().unwrap();
+            if let Some(job_state) = jobs.iter_mut().find(|j| j.job.id == id) {
+                job_state.job.output = Some(stem.to_string());
+            }
+        }
+        self.persist(id);
+    }
+
+    pub async fn release_stem(&self, id: &str) {
+        self.stems.lock().await.remove(id);
+    }
+
+    /// Output names of the downloads that are writing to `Songs/` right now.
+    pub async fn reserved_stems(&self) -> Vec<String> {
+        self.stems.lock().await.values().cloned().collect()
+    }
+
+    fn persist(&self, id: &str) {
+        let job = {
+            let jobs = self.jobs.lock().unwrap();
+            jobs.iter().find(|j| j.job.id == id).map(|j| j.job.clone())
+        };
+        if let Some(job) = job {
+            let _ = self.persist_tx.send(PersistOp::Save(Box::new(job)));
         }
     }
 
@@ -69,23 +357,150 @@ impl DownloadManager {
         }
     }
 
-    pub fn cancel_job(&self, id: &str) {
-        let mut jobs = self.jobs.lock().unwrap();
-        if let Some(job_state) = jobs.iter_mut().find(|j| j.job.id == id) {
+    /// Stops a job. A running one is cancelled by its worker once yt-dlp exits,
+    /// a paused one has no process and is cancelled here. The paused job is
+    /// returned, its partial download is still in `Songs/`.
+    pub fn cancel_job(&self, app: &AppHandle, id: &str) -> Option<DownloadJob> {
+        let paused = {
+            let mut jobs = self.jobs.lock().unwrap();
+            let job_state = jobs.iter_mut().find(|j| j.job.id == id)?;
+            if let Some(tx) = job_state.cancel_tx.take() {
+                let _ = tx.send(());
+                return None;
+            }
+            if job_state.job.status != JobStatus::Paused {
+                return None;
+            }
+            job_state.job.transition(JobStatus::Cancelled).ok()?;
+            job_state.job.detailed_status = None;
+            job_state.job.clone()
+        };
+        self.persist(id);
+        self.emit_update(app);
+        Some(paused)
+    }
+
+    /// Stops a job while keeping yt-dlp's `.part` file, so resuming continues the download.
+    pub fn pause_job(&self, app: &AppHandle, id: &str) -> Result<(), String> {
+        {
+            let mut jobs = self.jobs.lock().unwrap();
+            let job_state = jobs
+                .iter_mut()
+                .find(|j| j.job.id == id)
+                .ok_or("Download not found")?;
+            // Before yt-dlp starts or after it exits there is no process to stop
+            if job_state.job.status.is_active() && job_state.cancel_tx.is_none() {
+                return Err("Download can't be paused right now".into());
+            }
+            job_state.job.transition(JobStatus::Paused)?;
+            job_state.job.detailed_status = None;
             if let Some(tx) = job_state.cancel_tx.take() {
                 let _ = tx.send(());
             }
         }
+        self.persist(id);
+        self.emit_update(app);
+        Ok(())
+    }
+
+    pub fn resume_job(&self, app: &AppHandle, id: &str) -> Result<(), String> {
+        {
+            let mut jobs = self.jobs.lock().unwrap();
+            let job_state = jobs
+                .iter_mut()
+                .find(|j| j.job.id == id)
+                .ok_or("Download not found")?;
+            job_state.job.transition(JobStatus::Queued)?;
+            job_state.job.retry_at = None;
+        }
+        self.persist(id);
+        self.emit_update(app);
+        self.trigger_processing(app);
+        Ok(())
+    }
+
+    pub fn move_job(&self, app: &AppHandle, id: &str, direction: QueueMove) -> Result<(), String> {
+        let ids = {
+            let jobs = self.jobs.lock().unwrap();
+            let ids: Vec<String> = queue_order(&jobs)
+                .into_iter()
+                .map(|i| jobs[i].job.id.clone())
+                .collect();
+            if !ids.iter().any(|i| i == id) {
+                return Err("Download is not in the queue".into());
+            }
+            match moved(&ids, id, direction) {
+                Some(ids) => ids,
+                None => return Ok(()),
+            }
+        };
+        self.reorder(app, &ids)
+    }
+
+    /// Puts the queue in the order of `ids`. Queued jobs missing from `ids`
+    /// keep their relative order after the listed ones.
+    pub fn reorder(&self, app: &AppHandle, ids: &[String]) -> Result<(), String> {
+        let changed: Vec<String> = {
+            let mut jobs = self.jobs.lock().unwrap();
+            let order = queue_order(&jobs);
+            let mut ordered = Vec::with_capacity(order.len());
+            for id in ids {
+                let index = order
+                    .iter()
+                    .copied()
+                    .find(|&i| jobs[i].job.id == *id)
+                    .ok_or_else(|| format!("Download {} is not in the queue", id))?;
+                if !ordered.contains(&index) {
+                    ordered.push(index);
+                }
+            }
+            let rest: Vec<usize> = order.into_iter().filter(|i| !ordered.contains(i)).collect();
+            ordered.extend(rest);
+
+            // The last job gets priority 1, so newly added jobs (0) go after all of them
+            let count = ordered.len() as i64;
+            let mut changed = Vec::new();
+            for (position, index) in ordered.into_iter().enumerate() {
+                let job = &mut jobs[index].job;
+                let priority = count - position as i64;
+                if job.priority != priority {
+                    job.priority = priority;
+                    changed.push(job.id.clone());
+                }
+            }
+            changed
+        };
+        for id in &changed {
+            self.persist(id);
+        }
+        self.emit_update(app);
+        Ok(())
+    }
+
+    pub fn set_queue_paused(&self, app: &AppHandle, paused: bool) {
+        self.queue_paused.store(paused, Ordering::SeqCst);
+        let _ = app.emit("download://queue-paused", paused);
+        if !paused {
+            self.trigger_processing(app);
+        }
+    }
+
+    pub fn is_queue_paused(&self) -> bool {
+        self.queue_paused.load(Ordering::SeqCst)
     }
 
     pub fn add_job(&self, app: &AppHandle, job: DownloadJob) {
+        let id = job.id.clone();
         {
             let mut jobs = self.jobs.lock().unwrap();
+            // A re-queued song replaces its finished entry in the history
+            jobs.retain(|j| j.job.id != id);
             jobs.push(JobState {
                 job,
                 cancel_tx: None,
             });
         }
+        self.persist(&id);
         self.emit_update(app);
         self.trigger_processing(app);
     }
@@ -95,26 +510,108 @@ impl DownloadManager {
         jobs.iter().map(|j| j.job.clone()).collect()
     }
 
-    pub fn remove_job(&self, app: &AppHandle, id: &str) {
-        {
+    /// Drops a job from the list, returning it when it was paused so its
+    /// partial download can be deleted.
+    pub fn remove_job(&self, app: &AppHandle, id: &str) -> Option<DownloadJob> {
+        let removed = {
             let mut jobs = self.jobs.lock().unwrap();
-            jobs.retain(|j| j.job.id != id);
-        }
+            let index = jobs.iter().position(|j| j.job.id == id);
+            index.map(|i| jobs.remove(i).job)
+        };
+        let _ = self
+            .persist_tx
+            .send(PersistOp::Remove(vec![id.to_string()]));
         self.emit_update(app);
+        removed.filter(|job| job.status == JobStatus::Paused)
     }
 
     pub fn clear_history(&self, app: &AppHandle) {
-        {
+        self.remove_where(app, |job| job.status.is_finished());
+    }
+
+    /// Puts every failed job back in the queue with a fresh set of attempts.
+    pub fn retry_failed(&self, app: &AppHandle) -> usize {
+        let retried: Vec<String> = {
             let mut jobs = self.jobs.lock().unwrap();
-            jobs.retain(|j| j.job.status != "completed" && j.job.status != "error");
+            jobs.iter_mut()
+                .filter(|j| matches!(j.job.status, JobStatus::Failed { .. }))
+                .filter_map(|j| {
+                    j.job.transition(JobStatus::Queued).ok()?;
+                    j.job.attempts = 0;
+                    j.job.retry_at = None;
+                    j.job.progress = 0.0;
+                    j.job.detailed_status = None;
+                    j.job.logs.push("[retry] Re-queued manually".to_string());
+                    Some(j.job.id.clone())
+                })
+                .collect()
+        };
+        for id in &retried {
+            self.persist(id);
+        }
+        self.emit_update(app);
+        self.trigger_processing(app);
+        retried.len()
+    }
+
+    /// Re-queues a failed job after a backoff delay when the error may be
+    /// transient and attempts are left. Returns false when the job should fail.
+    fn schedule_retry(&self, app: &AppHandle, id: &str, error: &str) -> bool {
+        if !is_retryable(error) {
+            return false;
         }
+
+        let delay = {
+            let mut jobs = self.jobs.lock().unwrap();
+            let Some(job_state) = jobs.iter_mut().find(|j| j.job.id == id) else {
+                return false;
+            };
+            let job = &mut job_state.job;
+            if job.attempts >= self.retry.max_attempts {
+                return false;
+            }
+            if job.transition(JobStatus::Queued).is_err() {
+                return false;
+            }
+
+            let delay = self.retry.delay(job.attempts, jitter()).as_secs().max(1);
+            job.progress = 0.0;
+            job.retry_at = Some(now() + delay as i64);
+            job.detailed_status = Some(format!("Retrying in {}s", delay));
+            job.logs.push(format!(
+                "[retry] Attempt {} of {} failed: {}. Retrying in {}s",
+                job.attempts, self.retry.max_attempts, error, delay
+            ));
+            delay
+        };
+        self.persist(id);
         self.emit_update(app);
+
+        let app = app.clone();
+        tauri::async_runtime::spawn(async move {
+            tokio::time::sleep(Duration::from_secs(delay)).await;
+            app.state::<DownloadManager>().trigger_processing(&app);
+        });
+        true
     }
 
     pub fn clear_queue(&self, app: &AppHandle) {
-        {
+        self.remove_where(app, |job| job.status == JobStatus::Queued);
+    }
+
+    fn remove_where(&self, app: &AppHandle, matches: impl Fn(&DownloadJob) -> bool) {
+        let removed: Vec<String> = {
             let mut jobs = self.jobs.lock().unwrap();
-            jobs.retain(|j| j.job.status != "queued");
+            let removed = jobs
+                .iter()
+                .filter(|j| matches(&j.job))
+                .map(|j| j.job.id.clone())
+                .collect();
+            jobs.retain(|j| !matches(&j.job));
+            removed
+        };
+        if !removed.is_empty() {
+            let _ = self.persist_tx.send(PersistOp::Remove(removed));
         }
         self.emit_update(app);
     }
@@ -125,17 +622,41 @@ impl DownloadManager {
         let _ = app.emit("download://list-updated", download_jobs);
     }
 
-    pub fn update_job_status(&self, app: &AppHandle, id: &str, status: &str, progress: f64) {
-        {
+    /// Moves a job to `status`. Illegal transitions are logged and leave the job untouched.
+    pub fn update_job_status(&self, app: &AppHandle, id: &str, status: JobStatus, progress: f64) {
+        let changed = {
             let mut jobs = self.jobs.lock().unwrap();
-            if let Some(job_state) = jobs.iter_mut().find(|j| j.job.id == id) {
-                job_state.job.status = status.to_string();
-                job_state.job.progress = progress;
+            match jobs.iter_mut().find(|j| j.job.id == id) {
+                Some(job_state) => {
+                    let changed = job_state.job.status != status;
+                    match job_state.job.transition(status) {
+                        Ok(()) => {
+                            job_state.job.progress = progress;
+                            changed
+                        }
+                        Err(e) => {
+                            eprintln!("{}", e);
+                            return;
+                        }
+                    }
+                }
+                None => false,
             }
+        };
+        // Progress ticks stay in memory, only status changes are written out
+        if changed {
+            self.persist(id);
         }
         self.emit_update(app);
     }
 
+    pub fn job_status(&self, id: &str) -> Option<JobStatus> {
+        let jobs = self.jobs.lock().unwrap();
+        jobs.iter()
+            .find(|j| j.job.id == id)
+            .map(|j| j.job.status.clone())
+    }
+
     pub fn append_log(&self, id: &str, log: String) {
         let mut jobs = self.jobs.lock().unwrap();
         if let Some(job_state) = jobs.iter_mut().find(|j| j.job.id == id) {
@@ -158,63 +679,289 @@ impl DownloadManager {
     }
 }
 
+/// Keeps new downloads from starting until dropped, then restarts the queue.
+pub struct LibrarySwitch<'a> {
+    manager: &'a DownloadManager,
+    app: AppHandle,
+}
+
+impl Drop for LibrarySwitch<'_> {
+    fn drop(&mut self) {
+        self.manager.switching.store(false, Ordering::SeqCst);
+        self.manager.trigger_processing(&self.app);
+    }
+}
+
+/// Host of a job's URL, lowercased and without port or credentials.
+fn domain_of(url: &str) -> String {
+    let rest = url.split_once("://").map_or(url, |(_, rest)| rest);
+    let authority = rest.split(['/', '?', '#']).next().unwrap_or_default();
+    let host = authority.rsplit('@').next().unwrap_or_default();
+    let host = host.split(':').next().unwrap_or_default();
+    host.trim_start_matches("www.").to_lowercase()
+}
+
+/// Entry of `limits` that applies to `domain`, matching subdomains of an entry too.
+fn limit_for<'a>(limits: &'a BTreeMap<String, u32>, domain: &str) -> Option<(&'a str, u32)> {
+    limits
+        .iter()
+        .find(|(key, _)| domain == key.as_str() || domain.ends_with(&format!(".{}", key)))
+        .map(|(key, limit)| (key.as_str(), *limit))
+}
+
+/// Starts queued jobs until every worker slot is taken or nothing else may
+/// run. Each worker re-runs this when it finishes, so the next job starts right away.
 async fn process_download_queue(app: AppHandle) {
     let manager = app.state::<DownloadManager>();
+    if manager.is_queue_paused() {
+        return;
+    }
 
-    let next_job_info = {
-        let jobs_guard = manager.jobs.lock().unwrap();
-        let is_processing = jobs_guard
-            .iter()
-            .any(|j| j.job.status == "pending" || j.job.status == "downloading");
+    let config = {
+        let config_state = app.state::<crate::config::ConfigState>();
+        let config_guard = config_state.lock().unwrap();
+        match config_guard.as_ref() {
+            Some(config) => config.clone(),
+            None => return,
+        }
+    };
+    let max_workers = config.max_concurrent_downloads.max(1) as usize;
+    let limits = &config.domain_limits;
 
-        if is_processing {
+    let started: Vec<DownloadRequest> = {
+        let mut jobs_guard = manager.jobs.lock().unwrap();
+        if manager.switching.load(Ordering::SeqCst) {
             return;
         }
-
-        jobs_guard
+        // Limit entry of every running job, None for jobs on unlimited domains
+        let mut active: Vec<Option<&str>> = jobs_guard
             .iter()
-            .find(|j| j.job.status == "queued")
-            .map(|j| (j.job.id.clone(), j.job.url.clone(), j.job.metadata.clone()))
+            .filter(|j| j.job.status.is_active())
+            .map(|j| limit_for(limits, &domain_of(&j.job.url)).map(|(key, _)| key))
+            .collect();
+
+        let now = now();
+        let mut started = Vec::new();
+        for index in queue_order(&jobs_guard) {
+            let job_state = &mut jobs_guard[index];
+            if active.len() >= max_workers {
+                break;
+            }
+            if job_state.job.status != JobStatus::Queued {
+                continue;
+            }
+            if job_state.job.retry_at.is_some_and(|at| at > now) {
+                continue;
+            }
+
+            let limit = limit_for(limits, &domain_of(&job_state.job.url));
+            if let Some((key, max)) = limit {
+                let running = active.iter().filter(|k| **k == Some(key)).count();
+                if running >= max as usize {
+                    continue;
+                }
+            }
+
+            if let Err(e) = job_state.job.transition(JobStatus::Fetching) {
+                eprintln!("{}", e);
+                continue;
+            }
+            job_state.job.attempts += 1;
+            job_state.job.retry_at = None;
+            if job_state.job.attempts > 1 {
+                job_state.job.logs.push(format!(
+                    "[retry] Starting attempt {} of {}",
+                    job_state.job.attempts, manager.retry.max_attempts
+                ));
+            }
+            active.push(limit.map(|(key, _)| key));
+            // A job that was paused before has a partial download to continue from
+            let paused = job_state
+                .job
+                .transitions
+                .iter()
+                .any(|t| t.status == JobStatus::Paused);
+            let resume = job_state.job.output.clone().filter(|_| paused);
+            started.push(DownloadRequest {
+                id: job_state.job.id.clone(),
+                url: job_state.job.url.clone(),
+                metadata: job_state.job.metadata.clone(),
+                filename_template: config.filename_template.clone(),
+                profile: config.quality_profile(job_state.job.profile.as_deref()),
+                resume,
+            });
+        }
+        started
     };
 
-    if next_job_info.is_none() {
+    if started.is_empty() {
         return;
     }
+    for request in &started {
+        manager.persist(&request.id);
+    }
+    manager.emit_update(&app);
+
+    for request in started {
+        let app = app.clone();
+        tauri::async_runtime::spawn(async move { run_job(app, request).await });
+    }
+}
 
-    let (id, url, metadata) = next_job_info.unwrap();
+async fn run_job(app: AppHandle, request: DownloadRequest) {
+    let manager = app.state::<DownloadManager>();
+    let id = request.id.clone();
+    let result = run_download(app.clone(), request).await;
+    // The file is in the library now or never will be, either way the name is free
+    manager.release_stem(&id).await;
+
+    match result {
+        Ok(()) => manager.update_job_status(&app, &id, JobStatus::Completed, 100.0),
+        // Stopped by pause_job, the job already has its status
+        Err(_) if manager.job_status(&id) == Some(JobStatus::Paused) => {}
+        Err(e) => {
+            let error_msg = e.to_string();
+            let is_cancelled = error_msg == "Download cancelled";
+            if !is_cancelled && manager.schedule_retry(&app, &id, &error_msg) {
+                manager.trigger_processing(&app);
+                return;
+            }
 
-    {
-        let mut jobs_guard = manager.jobs.lock().unwrap();
-        if let Some(job_state) = jobs_guard.iter_mut().find(|j| j.job.id == id) {
-            job_state.job.status = "pending".into();
+            let status = if is_cancelled {
+                JobStatus::Cancelled
+            } else {
+                JobStatus::Failed {
+                    reason: error_msg.clone(),
+                }
+            };
+            manager.update_job_status(&app, &id, status, 0.0);
+
+            let _ = app.emit(
+                "download://error",
+                DownloadErrorPayload {
+                    id: id.clone(),
+                    error: error_msg,
+                    is_cancelled,
+                },
+            );
         }
     }
-    manager.emit_update(&app);
 
-    let config_state = app.state::<crate::config::ConfigState>();
-    let library_path = {
-        let config_guard = config_state.lock().unwrap();
-        config_guard.as_ref().unwrap().library_path.clone()
-    };
+    manager.trigger_processing(&app);
+}
 
-    let result = run_download(url, id.clone(), app.clone(), library_path, metadata).await;
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::download::types::MetadataPayload;
+
+    #[test]
+    fn test_queue_order() {
+        let job = |id: &str, priority: i64, status: JobStatus| {
+            let metadata: MetadataPayload = serde_json::from_value(serde_json::json!({
+                "id": id,
+                "url": "https://example.com",
+                "title": id,
+                "artist": "Artist",
+                "album": null,
+                "thumbnail": null,
+                "duration": null,
+            }))
+            .unwrap();
+            let mut job = DownloadJob::new(id.to_string(), metadata.url.clone(), metadata);
+            job.priority = priority;
+            job.status = status;
+            JobState {
+                job,
+                cancel_tx: None,
+            }
+        };
+        let jobs = vec![
+            job("a", 0, JobStatus::Queued),
+            job("b", 0, JobStatus::Completed),
+            job("c", 2, JobStatus::Queued),
+            job("d", 0, JobStatus::Paused),
+            job("e", 1, JobStatus::Queued),
+        ];
+        let ids: Vec<String> = queue_order(&jobs)
+            .into_iter()
+            .map(|i| jobs[i].job.id.clone())
+            .collect();
+        assert_eq!(ids, ["c", "e", "a", "d"]);
+
+        assert_eq!(
+            moved(&ids, "d", QueueMove::Top).unwrap(),
+            ["d", "c", "e", "a"]
+        );
+        assert_eq!(
+            moved(&ids, "a", QueueMove::Up).unwrap(),
+            ["c", "a", "e", "d"]
+        );
+        assert_eq!(
+            moved(&ids, "c", QueueMove::Down).unwrap(),
+            ["e", "c", "a", "d"]
+        );
+        assert!(moved(&ids, "c", QueueMove::Up).is_none());
+        assert!(moved(&ids, "d", QueueMove::Down).is_none());
+        assert!(moved(&ids, "b", QueueMove::Top).is_none());
+    }
 
-    if let Err(e) = result {
-        let error_msg = e.to_string();
-        let is_cancelled = error_msg == "Download cancelled";
+    #[test]
+    fn test_retry_policy() {
+        let policy = RetryPolicy::default();
+        assert_eq!(policy.delay(1, 0.0), Duration::from_millis(2500));
+        assert_eq!(policy.delay(1, 1.0), Duration::from_secs(5));
+        assert_eq!(policy.delay(3, 1.0), Duration::from_secs(20));
+        assert_eq!(policy.delay(30, 1.0), policy.max_delay);
+        assert_eq!(policy.delay(30, 0.0), policy.max_delay / 2);
+
+        assert!(is_retryable(
+            "ERROR: Unable to download webpage: HTTP Error 429: Too Many Requests"
+        ));
+        assert!(is_retryable(
+            "ERROR: [youtube] abc: Unable to extract uploader id"
+        ));
+        assert!(is_retryable(
+            "Download failed: ERROR: HTTP Error 503: Service Unavailable"
+        ));
+        assert!(!is_retryable("ERROR: [youtube] abc: Video unavailable"));
+        assert!(!is_retryable(
+            "ERROR: [youtube] abc: Private video. Sign in if you've been granted access"
+        ));
+        assert!(is_retryable("Download failed with exit code: None"));
+        assert!(!is_retryable("Download failed with exit code: Some(1)"));
+        assert!(!is_retryable(
+            "ERROR: unable to download video data: HTTP Error 403: Forbidden"
+        ));
+        assert!(!is_retryable(
+            "ERROR: [youtube] abc: The uploader has not made this video available in your country"
+        ));
+        assert!(!is_retryable("Failed to add song to database: disk full"));
+    }
 
-        manager.update_job_status(&app, &id, "error", 0.0);
+    #[test]
+    fn test_domain_limits() {
+        assert_eq!(
+            domain_of("https://www.youtube.com/watch?v=abc"),
+            "youtube.com"
+        );
+        assert_eq!(
+            domain_of("https://music.YouTube.com/watch"),
+            "music.youtube.com"
+        );
+        assert_eq!(
+            domain_of("http://user@soundcloud.com:8080/a"),
+            "soundcloud.com"
+        );
+        assert_eq!(domain_of("youtu.be/abc"), "youtu.be");
 
-        let _ = app.emit(
-            "download://error",
-            DownloadErrorPayload {
-                id: id.clone(),
-                error: error_msg,
-                is_cancelled,
-            },
+        let limits = BTreeMap::from([("youtube.com".to_string(), 2)]);
+        assert_eq!(limit_for(&limits, "youtube.com"), Some(("youtube.com", 2)));
+        assert_eq!(
+            limit_for(&limits, "music.youtube.com"),
+            Some(("youtube.com", 2))
         );
+        assert_eq!(limit_for(&limits, "notyoutube.com"), None);
+        assert_eq!(limit_for(&limits, "soundcloud.com"), None);
     }
-
-    manager.update_job_status(&app, &id, "completed", 100.0);
-    manager.trigger_processing(&app);
 }
@@ -1,5 +1,7 @@
+pub mod chapters;
 pub mod manager;
 pub mod process;
+pub mod title;
 pub mod types;
 
 pub use manager::*;
@@ -6,10 +6,17 @@ use tauri::{AppHandle, Emitter, Manager};
 use tokio::process::Command;
 
 #[cfg(windows)]
-const CREATE_NO_WINDOW: u32 = 0x08000000;
-
+use crate::bundler::CREATE_NO_WINDOW;
+use crate::db::entities::Song;
+use crate::db::Database;
+use crate::filename::{self, TemplateFields};
+use crate::integrity::AUDIO_EXTENSIONS;
+use crate::quality::{self, AudioFormat, QualityProfile};
+
+use super::chapters::{self, Chapter};
 use super::manager::DownloadManager;
-use super::types::{DownloadProgressPayload, MetadataPayload};
+use super::title;
+use super::types::{DownloadJob, DownloadProgressPayload, JobStatus, MetadataPayload};
 
 #[derive(Deserialize)]
 struct YtDlpOutput {
@@ -24,6 +31,15 @@ struct YtDlpOutput {
     album: Option<String>,
     thumbnail: Option<String>,
     duration: Option<f64>,
+    description: Option<String>,
+    chapters: Option<Vec<YtDlpChapter>>,
+}
+
+#[derive(Deserialize)]
+struct YtDlpChapter {
+    start_time: f64,
+    end_time: Option<f64>,
+    title: Option<String>,
 }
 
 pub async fn get_metadata(app: AppHandle, url: String) -> Result<Vec<MetadataPayload>, String> {
@@ -60,12 +76,10 @@ pub async fn get_metadata(app: AppHandle, url: String) -> Result<Vec<MetadataPay
     for entry in stream {
         let yt_data = entry.map_err(|e| format!("Failed to parse yt-dlp output: {}", e))?;
 
-        let title = yt_data.title.unwrap_or_else(|| "Unknown Title".into());
-        let artist = yt_data
-            .artist
-            .or(yt_data.creator)
-            .or(yt_data.uploader)
-            .unwrap_or_else(|| "Unknown Artist".into());
+        let raw_title = yt_data.title.unwrap_or_else(|| "Unknown Title".into());
+        let channel = yt_data.creator.or(yt_data.uploader);
+        let parsed = title::parse(&raw_title, yt_data.artist.as_deref(), channel.as_deref());
+        let featured_artists = Some(parsed.featured_artists.join(", ")).filter(|f| !f.is_empty());
 
         let video_url = yt_data
             .webpage_url
@@ -73,14 +87,39 @@ pub async fn get_metadata(app: AppHandle, url: String) -> Result<Vec<MetadataPay
             .or(yt_data.url)
             .unwrap_or_else(|| url.clone());
 
+        // Description tracklists stand in for videos uploaded without chapters
+        let chapters = match yt_data.chapters {
+            Some(chapters) if !chapters.is_empty() => chapters
+                .into_iter()
+                .enumerate()
+                .map(|(i, c)| Chapter {
+                    title: c.title.unwrap_or_else(|| format!("Track {}", i + 1)),
+                    start: c.start_time,
+                    end: c.end_time,
+                })
+                .collect(),
+            _ => yt_data
+                .description
+                .as_deref()
+                .map(|d| chapters::from_description(d, yt_data.duration))
+                .unwrap_or_default(),
+        };
+
         results.push(MetadataPayload {
             id: yt_data.id.unwrap_or_else(|| "unknown".into()),
             url: video_url,
-            title,
-            artist,
+            title: parsed.title,
+            artist: parsed.artist,
             album: yt_data.album,
             thumbnail: yt_data.thumbnail,
             duration: yt_data.duration,
+            version: parsed.version,
+            remixer: parsed.remixer,
+            featured_artists,
+            clip_start: None,
+            clip_end: None,
+            chapters,
+            track_number: None,
         });
     }
 
@@ -128,6 +167,18 @@ fn parse_log_status(line: &str) -> Option<String> {
     None
 }
 
+/// Whether yt-dlp has moved past downloading into its postprocessors.
+fn is_post_processing(line: &str) -> bool {
+    [
+        "[ExtractAudio]",
+        "[Metadata]",
+        "[ThumbnailsConvertor]",
+        "[EmbedThumbnail]",
+    ]
+    .iter()
+    .any(|tag| line.contains(tag))
+}
+
 fn get_ytdlp_paths(app: &AppHandle) -> Result<(PathBuf, PathBuf), anyhow::Error> {
     let app_data_dir = app
         .path()
@@ -149,16 +200,137 @@ fn get_ytdlp_paths(app: &AppHandle) -> Result<(PathBuf, PathBuf), anyhow::Error>
     Ok((bin_dir, ytdlp_path))
 }
 
-fn prepare_output_template(library_path: &str) -> Result<String, anyhow::Error> {
-    let songs_dir = std::path::Path::new(library_path).join("Songs");
-    if !songs_dir.exists() {
-        fs::create_dir_all(&songs_dir)?;
+fn database(app: &AppHandle) -> Result<Database, anyhow::Error> {
+    let db_state = app
+        .try_state::<Mutex<Option<Database>>>()
+        .ok_or_else(|| anyhow::anyhow!("Database state not found"))?;
+
+    let db_guard = db_state.lock().unwrap();
+    db_guard
+        .clone()
+        .ok_or_else(|| anyhow::anyhow!("Database not initialized"))
+}
+
+/// What the scheduler hands a worker to download.
+pub struct DownloadRequest {
+    pub id: String,
+    pub url: String,
+    pub metadata: MetadataPayload,
+    pub filename_template: String,
+    pub profile: QualityProfile,
+    /// Output of the paused attempt whose partial download is continued
+    pub resume: Option<String>,
+}
+
+/// Extensions the finished file can have, every audio type when it follows the source.
+fn output_extensions(format: AudioFormat) -> Vec<&'static str> {
+    match format.extension() {
+        Some(ext) => vec![ext],
+        None => AUDIO_EXTENSIONS.to_vec(),
+    }
+}
+
+/// Resolves the song's path under `Songs/` from the filename template, or keeps
+/// `previous` when continuing a paused download, and returns it without
+/// extension, together with the matching yt-dlp output template.
+async fn prepare_output_template(
+    manager: &DownloadManager,
+    db: &Database,
+    filename_template: &str,
+    id: &str,
+    metadata: &MetadataPayload,
+    format: AudioFormat,
+    previous: Option<&str>,
+) -> Result<(String, String), anyhow::Error> {
+    let rendered = match previous {
+        Some(previous) => previous.to_string(),
+        None => {
+            let fields = TemplateFields::from_song(&Song {
+                id: id.to_string(),
+                title: metadata.title.clone(),
+                artist: metadata.artist.clone(),
+                album: metadata.album.clone(),
+                version: metadata.version.clone(),
+                featured_artists: metadata.featured_artists.clone(),
+                ..Default::default()
+            });
+            filename::render(filename_template, &fields)
+        }
+    };
+    let stem = manager
+        .reserve_stem(db, &rendered, id, &output_extensions(format))
+        .await?;
+
+    let stem_path = Path::new(&db.library_path).join("Songs").join(&stem);
+    if let Some(parent) = stem_path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    // yt-dlp treats % as the start of a field, and picks the extension itself
+    let output_template = format!("{}.%(ext)s", stem_path.to_string_lossy().replace('%', "%%"));
+    Ok((stem, output_template))
+}
+
+/// Files yt-dlp keeps while downloading to `stem` under `dir`, left behind when it is stopped.
+fn partial_files(dir: &Path, stem: &str) -> Result<Vec<PathBuf>, std::io::Error> {
+    let path = dir.join(stem);
+    let (Some(parent), Some(name)) = (path.parent(), path.file_name()) else {
+        return Ok(Vec::new());
+    };
+    if !parent.is_dir() {
+        return Ok(Vec::new());
+    }
+    let prefix = format!("{}.", name.to_string_lossy());
+    let mut files = Vec::new();
+    for entry in fs::read_dir(parent)? {
+        let entry = entry?;
+        let file_name = entry.file_name().to_string_lossy().to_string();
+        let partial = file_name.ends_with(".part")
+            || file_name.ends_with(".ytdl")
+            || file_name.contains(".part-Frag");
+        if file_name.starts_with(&prefix) && partial {
+            files.push(entry.path());
+        }
+    }
+    Ok(files)
+}
+
+/// Deletes the partial download of a paused job that was cancelled or removed.
+pub async fn remove_partial_download(
+    app: &AppHandle,
+    job: &DownloadJob,
+) -> Result<(), anyhow::Error> {
+    // Without an output yt-dlp never started, so there is nothing on disk
+    let Some(ref stem) = job.output else {
+        return Ok(());
+    };
+    let db = database(app)?;
+    for path in partial_files(&Path::new(&db.library_path).join("Songs"), stem)? {
+        fs::remove_file(path)?;
+    }
+    Ok(())
+}
+
+/// Finds the file yt-dlp wrote for `stem` and returns its path under `Songs/`,
+/// converting it first when the profile's format is one yt-dlp can't write.
+async fn finish_output(
+    app: &AppHandle,
+    db: &Database,
+    stem: &str,
+    format: AudioFormat,
+) -> Result<String, anyhow::Error> {
+    let songs_dir = Path::new(&db.library_path).join("Songs");
+    if format == AudioFormat::Aiff {
+        let wav = songs_dir.join(format!("{}.wav", stem));
+        let aiff = songs_dir.join(format!("{}.aiff", stem));
+        quality::to_aiff(app, &wav, &aiff).await?;
     }
 
-    Ok(format!(
-        "{}/%(title).150s-%(id).50s.%(ext)s",
-        songs_dir.to_string_lossy()
-    ))
+    output_extensions(format)
+        .into_iter()
+        .map(|ext| format!("{}.{}", stem, ext))
+        .find(|filename| songs_dir.join(filename).exists())
+        .ok_or_else(|| anyhow::anyhow!("yt-dlp did not produce an audio file for {}", stem))
 }
 
 fn construct_download_cmd(
@@ -166,6 +338,9 @@ fn construct_download_cmd(
     bin_dir: &Path,
     output_template: &str,
     url: &str,
+    profile: &QualityProfile,
+    section: Option<&str>,
+    resume: bool,
 ) -> Command {
     let mut cmd = Command::new(ytdlp_path);
     let current_path = std::env::var_os("PATH").unwrap_or_default();
@@ -180,18 +355,16 @@ fn construct_download_cmd(
     new_path.push_str(&current_path.to_string_lossy());
 
     cmd.env("PATH", new_path);
+    cmd.args(profile.ytdlp_args());
+    if let Some(section) = section {
+        // Cut at exact times rather than the nearest keyframes
+        cmd.args(["--download-sections", section, "--force-keyframes-at-cuts"]);
+    }
     cmd.args([
-        "--restrict-filenames",
-        "-x",
-        "--audio-format",
-        "mp3",
-        "--audio-quality",
-        "320k",
         "--ffmpeg-location",
         &bin_dir.to_string_lossy(),
         "--js-runtimes",
         "bun",
-        "--embed-thumbnail",
         "--embed-metadata",
         "--compat-options",
         "no-youtube-unavailable-videos",
@@ -202,6 +375,9 @@ fn construct_download_cmd(
         "download-progress:%(progress._percent_str)s",
         url,
     ]);
+    if resume {
+        cmd.arg("--continue");
+    }
 
     #[cfg(windows)]
     cmd.creation_flags(CREATE_NO_WINDOW);
@@ -209,85 +385,90 @@ fn construct_download_cmd(
     cmd
 }
 
-async fn get_final_filename(
-    ytdlp_path: &Path,
-    bin_dir: &Path,
-    output_template: &str,
-    url: &str,
-) -> Result<String, anyhow::Error> {
-    let mut cmd = Command::new(ytdlp_path);
-    cmd.args([
-        "--ffmpeg-location",
-        &bin_dir.to_string_lossy(),
-        "--restrict-filenames",
-        "-o",
-        output_template,
-        "--get-filename",
-        url,
-    ]);
-
-    #[cfg(windows)]
-    cmd.creation_flags(CREATE_NO_WINDOW);
-
-    let output = cmd.output().await?;
-
-    let filename = String::from_utf8_lossy(&output.stdout).trim().to_string();
-    if filename.is_empty() {
-        return Err(anyhow::anyhow!("Failed to resolve downloaded filename"));
-    }
-
-    Ok(filename)
-}
-
 async fn add_song_to_db(
     app: &AppHandle,
     id: String,
     metadata: MetadataPayload,
     filename: String,
     url: String,
+    quality_profile: String,
 ) -> Result<(), anyhow::Error> {
-    let path = PathBuf::from(filename);
-    let final_path = path.with_extension("mp3");
-
-    let db_state = app
-        .try_state::<Mutex<Option<crate::db::Database>>>()
-        .ok_or_else(|| anyhow::anyhow!("Database state not found"))?;
-
-    let db = {
-        let db_guard = db_state.lock().unwrap();
-        db_guard
-            .clone()
-            .ok_or_else(|| anyhow::anyhow!("Database not initialized"))?
-    };
+    let db = database(app)?;
+    let final_path = Path::new(&db.library_path).join("Songs").join(&filename);
 
-    let song = crate::db::entities::Song {
+    let mut song = Song {
         id: id.clone(),
         title: metadata.title,
         artist: metadata.artist,
         album: metadata.album,
-        filename: final_path
-            .file_name()
-            .unwrap()
-            .to_string_lossy()
-            .to_string(),
+        filename,
         source_url: Some(url),
-        tags: None,
+        duration: metadata.duration,
+        downloaded_at: std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .ok()
+            .map(|d| d.as_secs() as i64),
+        version: metadata.version,
+        remixer: metadata.remixer,
+        featured_artists: metadata.featured_artists,
+        clip_start: metadata.clip_start,
+        clip_end: metadata.clip_end,
+        track_number: metadata.track_number,
+        quality_profile: Some(quality_profile),
+        ..Default::default()
     };
 
+    // yt-dlp embeds the video's own metadata, replace it with what the user confirmed
+    if let Err(e) = crate::tagging::write_tags(app, &final_path, &song).await {
+        eprintln!("Failed to tag {}: {}", final_path.display(), e);
+    }
+
+    match crate::hashing::hash_file_async(&final_path).await {
+        Ok((hash, mtime)) => {
+            song.content_hash = Some(hash);
+            song.file_mtime = mtime;
+        }
+        Err(e) => eprintln!("Failed to hash {}: {}", final_path.display(), e),
+    }
+
+    // A re-download in another format would otherwise leave the old file behind
+    if let Ok(Some(existing)) = db.get_song_by_id(&id).await {
+        if existing.filename != song.filename {
+            let _ = fs::remove_file(
+                Path::new(&db.library_path)
+                    .join("Songs")
+                    .join(&existing.filename),
+            );
+        }
+    }
+
     db.add_song(&song)
         .await
         .map_err(|e| anyhow::anyhow!("Failed to add song to database: {}", e))?;
 
+    // Regenerated rather than reused, the file may be a re-download with a new cover
+    crate::artwork::remove(&db.library_path, &id);
+    if let Err(e) = crate::artwork::generate(app, &db, &id).await {
+        eprintln!("Failed to generate artwork for {}: {}", id, e);
+    }
+
     let _ = app.emit("library://updated", ());
     Ok(())
 }
 
 fn process_stdout_line(app: &AppHandle, manager: &DownloadManager, id: &str, line: &str) {
+    if is_post_processing(line) {
+        manager.update_job_status(app, id, JobStatus::PostProcessing, 100.0);
+    }
+
     let detailed_status = parse_log_status(line);
+    let Some(status) = manager.job_status(id) else {
+        return;
+    };
     let log_payload = DownloadProgressPayload {
         id: id.to_string(),
         progress: -1.0,
-        status: "downloading".into(),
+        status,
         detailed_status: detailed_status.clone(),
         log: Some(line.to_string()),
     };
@@ -302,13 +483,13 @@ fn process_stdout_line(app: &AppHandle, manager: &DownloadManager, id: &str, lin
                 let progress_payload = DownloadProgressPayload {
                     id: id.to_string(),
                     progress: percentage,
-                    status: "downloading".into(),
+                    status: JobStatus::Downloading,
                     detailed_status: Some("Downloading".to_string()),
                     log: None,
                 };
                 let _ = app.emit("download://progress", progress_payload);
 
-                manager.update_job_status(app, id, "downloading", percentage);
+                manager.update_job_status(app, id, JobStatus::Downloading, percentage);
             }
         }
     }
@@ -316,10 +497,13 @@ fn process_stdout_line(app: &AppHandle, manager: &DownloadManager, id: &str, lin
 
 fn process_stderr_line(app: &AppHandle, manager: &DownloadManager, id: &str, line: &str) {
     let detailed_status = parse_log_status(line);
+    let Some(status) = manager.job_status(id) else {
+        return;
+    };
     let log_payload = DownloadProgressPayload {
         id: id.to_string(),
         progress: -1.0,
-        status: "downloading".into(),
+        status,
         detailed_status: detailed_status.clone(),
         log: Some(format!("[stderr] {}", line)),
     };
@@ -330,25 +514,48 @@ fn process_stderr_line(app: &AppHandle, manager: &DownloadManager, id: &str, lin
     manager.update_detailed_status(id, detailed_status);
 }
 
-pub async fn run_download(
-    url: String,
-    id: String,
-    app: AppHandle,
-    library_path: String,
-    metadata: MetadataPayload,
-) -> Result<(), anyhow::Error> {
+pub async fn run_download(app: AppHandle, request: DownloadRequest) -> Result<(), anyhow::Error> {
+    let DownloadRequest {
+        id,
+        url,
+        metadata,
+        filename_template,
+        profile,
+        resume,
+    } = request;
     let (bin_dir, ytdlp_path) = get_ytdlp_paths(&app)?;
 
-    let output_template = prepare_output_template(&library_path)?;
-
-    let mut cmd = construct_download_cmd(&ytdlp_path, &bin_dir, &output_template, &url);
-
+    let db = database(&app)?;
     let manager = app.state::<DownloadManager>();
+    let (stem, output_template) = prepare_output_template(
+        &manager,
+        &db,
+        &filename_template,
+        &id,
+        &metadata,
+        profile.format,
+        resume.as_deref(),
+    )
+    .await?;
+
+    let section = metadata.download_section();
+    let mut cmd = construct_download_cmd(
+        &ytdlp_path,
+        &bin_dir,
+        &output_template,
+        &url,
+        &profile,
+        section.as_deref(),
+        resume.is_some(),
+    );
+
     let (mut child, mut stdout_reader, mut stderr_reader, mut cancel_rx) =
         manager.create_process(&id, &mut cmd)?;
 
     use tokio::io::AsyncBufReadExt;
 
+    // yt-dlp's own description of a failure, used to decide whether to retry
+    let mut last_error = None;
     let status = loop {
         let mut stdout_buf = Vec::new();
         let mut stderr_buf = Vec::new();
@@ -369,6 +576,9 @@ pub async fn run_download(
                     Ok(0) => break child.wait().await?,
                     Ok(_) => {
                         let line = String::from_utf8_lossy(&stderr_buf);
+                        if line.starts_with("ERROR:") {
+                            last_error = Some(line.trim().to_string());
+                        }
                         process_stderr_line(&app, &manager, &id, line.trim());
                     }
                     Err(e) => return Err(e.into()),
@@ -387,15 +597,56 @@ pub async fn run_download(
         manager.finish_process(&id);
     }
 
+    // Paused just as yt-dlp exited, keep what's on disk for the resume
+    if manager.job_status(&id) == Some(JobStatus::Paused) {
+        return Err(anyhow::anyhow!("Download paused"));
+    }
+
     if !status.success() {
-        return Err(anyhow::anyhow!(
-            "Download failed with exit code: {:?}",
-            status.code()
-        ));
+        return Err(match last_error {
+            Some(error) => anyhow::anyhow!("Download failed: {}", error),
+            None => anyhow::anyhow!("Download failed with exit code: {:?}", status.code()),
+        });
     }
 
-    let filename = get_final_filename(&ytdlp_path, &bin_dir, &output_template, &url).await?;
-    add_song_to_db(&app, id, metadata, filename, url).await?;
+    manager.update_job_status(&app, &id, JobStatus::PostProcessing, 100.0);
+    let filename = finish_output(&app, &db, &stem, profile.format).await?;
+    add_song_to_db(&app, id, metadata, filename, url, profile.name).await?;
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_partial_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let songs = dir.path().join("Artist");
+        fs::create_dir_all(&songs).unwrap();
+        for name in [
+            "Song.webm.part",
+            "Song.webm.ytdl",
+            "Song.f251.webm.part-Frag3",
+            "Song.mp3",
+            "Song (2).webm.part",
+        ] {
+            fs::write(songs.join(name), b"").unwrap();
+        }
+
+        let mut files = partial_files(dir.path(), "Artist/Song").unwrap();
+        files.sort();
+        assert_eq!(
+            files,
+            [
+                songs.join("Song.f251.webm.part-Frag3"),
+                songs.join("Song.webm.part"),
+                songs.join("Song.webm.ytdl"),
+            ]
+        );
+        assert!(partial_files(dir.path(), "Missing/Song")
+            .unwrap()
+            .is_empty());
+    }
+}
@@ -0,0 +1,658 @@
+//! Cleans up the artist and title of a video into the track they describe.
+
+/// Words that only describe the upload, e.g. "(Official Music Video)" or "[HD]".
+const NOISE_WORDS: [&str; 24] = [
+    "official",
+    "officiel",
+    "oficial",
+    "music",
+    "video",
+    "audio",
+    "lyric",
+    "lyrics",
+    "visualizer",
+    "visualiser",
+    "hd",
+    "hq",
+    "4k",
+    "1080p",
+    "720p",
+    "clip",
+    "mv",
+    "m/v",
+    "explicit",
+    "free",
+    "download",
+    "out",
+    "now",
+    "premiere",
+];
+
+/// Filler allowed around noise words, as in "(Video with Lyrics)".
+const FILLER_WORDS: [&str; 4] = ["with", "the", "in", "&"];
+
+/// Final words that mark an annotation as a version of the track.
+const VERSION_WORDS: [&str; 15] = [
+    "remix",
+    "mix",
+    "edit",
+    "bootleg",
+    "rework",
+    "flip",
+    "refix",
+    "vip",
+    "dub",
+    "version",
+    "remaster",
+    "remastered",
+    "instrumental",
+    "acoustic",
+    "reprise",
+];
+
+/// Version prefixes that describe the kind of mix rather than who made it.
+const GENERIC_VERSIONS: [&str; 16] = [
+    "extended",
+    "radio",
+    "original",
+    "club",
+    "dub",
+    "vip",
+    "instrumental",
+    "short",
+    "long",
+    "album",
+    "single",
+    "clean",
+    "dirty",
+    "acoustic",
+    "festival",
+    "special",
+];
+
+const FEAT_MARKERS: [&str; 5] = ["featuring ", "feat. ", "feat ", "ft. ", "ft "];
+
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct ParsedTitle {
+    pub artist: String,
+    pub title: String,
+    pub featured_artists: Vec<String>,
+    /// Remix or edit annotation, e.g. "Extended Mix" or "Fred again.. Remix"
+    pub version: Option<String>,
+    /// Who made the version, when it names someone
+    pub remixer: Option<String>,
+}
+
+/// Strips the " - Topic" and "VEVO" decorations YouTube adds to channel names.
+pub fn clean_channel(name: &str) -> String {
+    let mut name = name.trim();
+    if let Some(stripped) = name.strip_suffix(" - Topic") {
+        name = stripped.trim_end();
+    }
+    for suffix in ["VEVO", "Vevo"] {
+        if let Some(stripped) = name.strip_suffix(suffix) {
+            if !stripped.trim().is_empty() {
+                name = stripped.trim_end();
+            }
+        }
+    }
+    name.to_string()
+}
+
+fn words(text: &str) -> Vec<String> {
+    text.split(|c: char| c.is_whitespace() || c == '-' || c == ',')
+        .filter(|w| !w.is_empty())
+        .map(|w| w.to_lowercase())
+        .collect()
+}
+
+fn is_noise(text: &str) -> bool {
+    let words = words(text);
+    words.iter().any(|w| NOISE_WORDS.contains(&w.as_str()))
+        && words
+            .iter()
+            .all(|w| NOISE_WORDS.contains(&w.as_str()) || FILLER_WORDS.contains(&w.as_str()))
+}
+
+fn is_version(text: &str) -> bool {
+    words(text)
+        .iter()
+        .any(|w| VERSION_WORDS.contains(&w.as_str()))
+}
+
+/// Splits "Artist feat. Guest" into the main part and the featured names.
+fn split_featured(text: &str) -> (String, Vec<String>) {
+    // ASCII-only so byte offsets in `lower` still line up with `text`
+    let lower = text.to_ascii_lowercase();
+    let found = FEAT_MARKERS
+        .iter()
+        .filter_map(|marker| {
+            let at = if lower.starts_with(marker) {
+                Some(0)
+            } else {
+                lower.find(&format!(" {}", marker)).map(|i| i + 1)
+            };
+            at.map(|i| (i, marker.len()))
+        })
+        .min_by_key(|(i, _)| *i);
+
+    match found {
+        Some((i, len)) => {
+            let names = text[i + len..]
+                .split([',', '&'])
+                .flat_map(|n| n.split(" and "))
+                .map(|n| n.trim().to_string())
+                .filter(|n| !n.is_empty())
+                .collect();
+            (text[..i].trim().to_string(), names)
+        }
+        None => (text.trim().to_string(), Vec::new()),
+    }
+}
+
+/// Separates bracketed groups from the rest of the text.
+fn split_groups(text: &str) -> (String, Vec<String>) {
+    let mut rest = String::new();
+    let mut groups = Vec::new();
+    let mut current = String::new();
+    let mut depth = 0;
+
+    for c in text.chars() {
+        match c {
+            '(' | '[' | '【' => {
+                if depth > 0 {
+                    current.push(c);
+                }
+                depth += 1;
+            }
+            ')' | ']' | '】' if depth > 0 => {
+                depth -= 1;
+                if depth == 0 {
+                    groups.push(current.trim().to_string());
+                    current.clear();
+                } else {
+                    current.push(c);
+                }
+            }
+            _ if depth > 0 => current.push(c),
+            _ => rest.push(c),
+        }
+    }
+    // An unclosed bracket is kept as part of the title
+    if !current.is_empty() {
+        rest.push_str(&current);
+    }
+    (rest, groups)
+}
+
+fn normalise(text: &str) -> String {
+    text.replace(['–', '—'], "-")
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn remixer_of(version: &str) -> Option<String> {
+    let mut words: Vec<&str> = version.split_whitespace().collect();
+    let last = words.pop()?.to_lowercase();
+    if !["remix", "edit", "bootleg", "rework", "flip", "refix", "mix"].contains(&last.as_str()) {
+        return None;
+    }
+    // "Someone Extended Remix" was made by Someone
+    while words
+        .last()
+        .is_some_and(|w| GENERIC_VERSIONS.contains(&w.to_lowercase().as_str()))
+    {
+        words.pop();
+    }
+    if words.is_empty() {
+        return None;
+    }
+    Some(words.join(" "))
+}
+
+/// Parses a video title into artist, title and credits. `artist` is the
+/// artist yt-dlp reported, if any, and `channel` the uploader to fall back on.
+pub fn parse(raw: &str, artist: Option<&str>, channel: Option<&str>) -> ParsedTitle {
+    let text = normalise(raw);
+    let (rest, groups) = split_groups(&text);
+
+    let mut featured = Vec::new();
+    let mut version = None;
+    let mut kept = Vec::new();
+    for group in groups {
+        let (main, names) = split_featured(&group);
+        if !names.is_empty() && main.is_empty() {
+            featured.extend(names);
+        } else if is_noise(&group) {
+            continue;
+        } else if is_version(&group) && version.is_none() {
+            version = Some(group);
+        } else {
+            kept.push(group);
+        }
+    }
+
+    // "Artist - Track | Label" and "Artist - Track // Label" carry extras after the bar
+    let rest = rest
+        .split(" | ")
+        .next()
+        .and_then(|r| r.split(" // ").next())
+        .unwrap_or_default()
+        .trim()
+        .to_string();
+
+    let mut segments: Vec<String> = rest
+        .split(" - ")
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty())
+        .collect();
+    while segments.len() > 1 {
+        let last = segments.last().map(String::as_str).unwrap_or_default();
+        if is_noise(last) {
+            segments.pop();
+        } else if is_version(last) && version.is_none() {
+            version = segments.pop();
+        } else {
+            break;
+        }
+    }
+
+    let known_artist = artist.map(str::trim).filter(|a| !a.is_empty());
+    let (artist, title) = match known_artist {
+        Some(known) => {
+            if segments.len() > 1 && segments[0].eq_ignore_ascii_case(known) {
+                segments.remove(0);
+            }
+            (known.to_string(), segments.join(" - "))
+        }
+        None if segments.len() > 1 => {
+            let artist = segments.remove(0);
+            (artist, segments.join(" - "))
+        }
+        None => (
+            channel
+                .map(clean_channel)
+                .filter(|c| !c.is_empty())
+                .unwrap_or_else(|| "Unknown Artist".to_string()),
+            segments.join(" - "),
+        ),
+    };
+
+    let (artist, artist_featured) = split_featured(&artist);
+    let (title, title_featured) = split_featured(&title);
+    for name in artist_featured.into_iter().chain(title_featured) {
+        if !featured
+            .iter()
+            .any(|f: &String| f.eq_ignore_ascii_case(&name))
+        {
+            featured.push(name);
+        }
+    }
+
+    let mut title = title;
+    for group in kept {
+        title = format!("{} ({})", title, group);
+    }
+    let title = title.trim().to_string();
+    let title = if title.is_empty() { text } else { title };
+
+    let remixer = version.as_deref().and_then(remixer_of);
+
+    ParsedTitle {
+        artist,
+        title,
+        featured_artists: featured,
+        version,
+        remixer,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Case {
+        raw: &'static str,
+        artist: Option<&'static str>,
+        channel: Option<&'static str>,
+        expected_artist: &'static str,
+        expected_title: &'static str,
+        featured: &'static [&'static str],
+        version: Option<&'static str>,
+        remixer: Option<&'static str>,
+    }
+
+    const fn case(
+        raw: &'static str,
+        expected_artist: &'static str,
+        expected_title: &'static str,
+    ) -> Case {
+        Case {
+            raw,
+            artist: None,
+            channel: None,
+            expected_artist,
+            expected_title,
+            featured: &[],
+            version: None,
+            remixer: None,
+        }
+    }
+
+    fn cases() -> Vec<Case> {
+        vec![
+            case("Dynoro - In My Mind", "Dynoro", "In My Mind"),
+            case(
+                "Dynoro - In My Mind (Official Video)",
+                "Dynoro",
+                "In My Mind",
+            ),
+            case(
+                "Dynoro - In My Mind (Official Music Video) [HD]",
+                "Dynoro",
+                "In My Mind",
+            ),
+            case("Dynoro – In My Mind (Lyrics)", "Dynoro", "In My Mind"),
+            case(
+                "Dynoro — In My Mind [Official Audio]",
+                "Dynoro",
+                "In My Mind",
+            ),
+            case(
+                "Dynoro - In My Mind (Official Lyric Video)",
+                "Dynoro",
+                "In My Mind",
+            ),
+            case(
+                "Dynoro - In My Mind (Video with Lyrics)",
+                "Dynoro",
+                "In My Mind",
+            ),
+            case("Dynoro - In My Mind [4K]", "Dynoro", "In My Mind"),
+            case(
+                "Dynoro - In My Mind (Official Visualizer)",
+                "Dynoro",
+                "In My Mind",
+            ),
+            case("Dynoro - In My Mind (Audio)", "Dynoro", "In My Mind"),
+            case(
+                "Stromae - Alors on danse (Clip Officiel)",
+                "Stromae",
+                "Alors on danse",
+            ),
+            case(
+                "Bad Bunny - Tití Me Preguntó (Video Oficial)",
+                "Bad Bunny",
+                "Tití Me Preguntó",
+            ),
+            case(
+                "Dynoro - In My Mind | Official Video",
+                "Dynoro",
+                "In My Mind",
+            ),
+            case(
+                "Dynoro - In My Mind // Spinnin' Records",
+                "Dynoro",
+                "In My Mind",
+            ),
+            case(
+                "Dynoro - In My Mind - Official Video",
+                "Dynoro",
+                "In My Mind",
+            ),
+            case("Fisher - Losing It [Free Download]", "Fisher", "Losing It"),
+            case("Fisher - Losing It (Out Now)", "Fisher", "Losing It"),
+            case("  Fisher  -   Losing   It  ", "Fisher", "Losing It"),
+            case(
+                "Daft Punk - Harder, Better, Faster, Stronger",
+                "Daft Punk",
+                "Harder, Better, Faster, Stronger",
+            ),
+            case(
+                "Simon & Garfunkel - The Sound of Silence",
+                "Simon & Garfunkel",
+                "The Sound of Silence",
+            ),
+            case("Sigur Rós - Hoppípolla", "Sigur Rós", "Hoppípolla"),
+            case(
+                "Bicep - Glue (Live at Printworks)",
+                "Bicep",
+                "Glue (Live at Printworks)",
+            ),
+            case("Artist - Track - Part 2", "Artist", "Track - Part 2"),
+            case("Artist - Track (Unclosed", "Artist", "Track Unclosed"),
+            Case {
+                featured: &["Sia"],
+                ..case(
+                    "David Guetta feat. Sia - Titanium",
+                    "David Guetta",
+                    "Titanium",
+                )
+            },
+            Case {
+                featured: &["Sia"],
+                ..case(
+                    "David Guetta ft. Sia - Titanium (Official Video)",
+                    "David Guetta",
+                    "Titanium",
+                )
+            },
+            Case {
+                featured: &["Sia"],
+                ..case(
+                    "David Guetta - Titanium (feat. Sia)",
+                    "David Guetta",
+                    "Titanium",
+                )
+            },
+            Case {
+                featured: &["Sia"],
+                ..case(
+                    "David Guetta - Titanium ft. Sia",
+                    "David Guetta",
+                    "Titanium",
+                )
+            },
+            Case {
+                featured: &["Sia"],
+                ..case(
+                    "David Guetta - Titanium [Feat. Sia] [Official Video]",
+                    "David Guetta",
+                    "Titanium",
+                )
+            },
+            Case {
+                featured: &["Kendrick Lamar", "SZA"],
+                ..case(
+                    "Artist - Song (featuring Kendrick Lamar & SZA)",
+                    "Artist",
+                    "Song",
+                )
+            },
+            Case {
+                featured: &["A", "B", "C"],
+                ..case("Artist - Song (feat. A, B and C)", "Artist", "Song")
+            },
+            Case {
+                featured: &["Sia"],
+                ..case(
+                    "David Guetta feat. Sia - Titanium (feat. Sia)",
+                    "David Guetta",
+                    "Titanium",
+                )
+            },
+            Case {
+                featured: &["Ö"],
+                ..case("İİİ feat. Ö - Şarkı", "İİİ", "Şarkı")
+            },
+            Case {
+                featured: &["Ö"],
+                ..case(
+                    "Sezen Aksu - İstanbul İstanbul Olalı feat. Ö",
+                    "Sezen Aksu",
+                    "İstanbul İstanbul Olalı",
+                )
+            },
+            Case {
+                featured: &["Ozuna"],
+                ..case("Artist Featuring Ozuna - Song", "Artist", "Song")
+            },
+            case("Lift - Song", "Lift", "Song"),
+            case("Draft Punk - Song", "Draft Punk", "Song"),
+            Case {
+                version: Some("Extended Mix"),
+                ..case("Artist - Track (Extended Mix)", "Artist", "Track")
+            },
+            Case {
+                version: Some("Original Mix"),
+                ..case(
+                    "Artist - Track (Original Mix) [Official Audio]",
+                    "Artist",
+                    "Track",
+                )
+            },
+            Case {
+                version: Some("Radio Edit"),
+                ..case("Artist - Track - Radio Edit", "Artist", "Track")
+            },
+            Case {
+                version: Some("Fred again.. Remix"),
+                remixer: Some("Fred again.."),
+                ..case("Four Tet - Baby (Fred again.. Remix)", "Four Tet", "Baby")
+            },
+            Case {
+                version: Some("Purple Disco Machine Remix"),
+                remixer: Some("Purple Disco Machine"),
+                ..case(
+                    "Dua Lipa - Hallucinate [Purple Disco Machine Remix] (Official Audio)",
+                    "Dua Lipa",
+                    "Hallucinate",
+                )
+            },
+            Case {
+                version: Some("Skrillex Bootleg"),
+                remixer: Some("Skrillex"),
+                ..case("Artist - Track (Skrillex Bootleg)", "Artist", "Track")
+            },
+            Case {
+                version: Some("John Summit Extended Edit"),
+                remixer: Some("John Summit"),
+                ..case(
+                    "Artist - Track (John Summit Extended Edit)",
+                    "Artist",
+                    "Track",
+                )
+            },
+            Case {
+                version: Some("VIP"),
+                ..case("Artist - Track (VIP)", "Artist", "Track")
+            },
+            Case {
+                version: Some("Remastered 2009"),
+                ..case(
+                    "The Beatles - Come Together (Remastered 2009)",
+                    "The Beatles",
+                    "Come Together",
+                )
+            },
+            Case {
+                version: Some("Acoustic Version"),
+                ..case("Artist - Track (Acoustic Version)", "Artist", "Track")
+            },
+            Case {
+                featured: &["Guest"],
+                version: Some("Someone Remix"),
+                remixer: Some("Someone"),
+                ..case(
+                    "Artist - Track (feat. Guest) (Someone Remix) [Official Video]",
+                    "Artist",
+                    "Track",
+                )
+            },
+            Case {
+                channel: Some("DynoroVEVO"),
+                ..case("In My Mind (Official Video)", "Dynoro", "In My Mind")
+            },
+            Case {
+                channel: Some("Dynoro - Topic"),
+                ..case("In My Mind", "Dynoro", "In My Mind")
+            },
+            Case {
+                channel: Some("SomeLabelVEVO"),
+                ..case("Artist - Track (Official Video) [HD]", "Artist", "Track")
+            },
+            Case {
+                channel: Some("VEVO"),
+                ..case("Track", "VEVO", "Track")
+            },
+            case("Track", "Unknown Artist", "Track"),
+            case("(Official Video)", "Unknown Artist", "(Official Video)"),
+            Case {
+                artist: Some("Dynoro"),
+                ..case("In My Mind", "Dynoro", "In My Mind")
+            },
+            Case {
+                artist: Some("Dynoro"),
+                ..case(
+                    "Dynoro - In My Mind (Official Video)",
+                    "Dynoro",
+                    "In My Mind",
+                )
+            },
+            Case {
+                artist: Some("Dynoro"),
+                ..case("dynoro - In My Mind", "Dynoro", "In My Mind")
+            },
+            Case {
+                artist: Some("Dynoro"),
+                channel: Some("SomeLabelVEVO"),
+                ..case("Intro - Outro", "Dynoro", "Intro - Outro")
+            },
+            Case {
+                artist: Some("Dynoro"),
+                version: Some("Extended Mix"),
+                ..case("In My Mind - Extended Mix", "Dynoro", "In My Mind")
+            },
+        ]
+    }
+
+    #[test]
+    fn test_parse_table() {
+        for case in cases() {
+            let parsed = parse(case.raw, case.artist, case.channel);
+            assert_eq!(
+                parsed.artist, case.expected_artist,
+                "artist of {:?}",
+                case.raw
+            );
+            assert_eq!(parsed.title, case.expected_title, "title of {:?}", case.raw);
+            assert_eq!(
+                parsed.featured_artists, case.featured,
+                "featured of {:?}",
+                case.raw
+            );
+            assert_eq!(
+                parsed.version.as_deref(),
+                case.version,
+                "version of {:?}",
+                case.raw
+            );
+            assert_eq!(
+                parsed.remixer.as_deref(),
+                case.remixer,
+                "remixer of {:?}",
+                case.raw
+            );
+        }
+    }
+
+    #[test]
+    fn test_clean_channel() {
+        assert_eq!(clean_channel("Dynoro - Topic"), "Dynoro");
+        assert_eq!(clean_channel("DynoroVEVO"), "Dynoro");
+        assert_eq!(clean_channel("Dynoro Vevo"), "Dynoro");
+        assert_eq!(clean_channel("VEVO"), "VEVO");
+        assert_eq!(clean_channel(" Spinnin' Records "), "Spinnin' Records");
+    }
+}
@@ -1,10 +1,23 @@
 use serde::{Deserialize, Serialize};
 
+use super::chapters::Chapter;
+use crate::db::entities::DownloadRecord;
+
+/// Prefix of the log lines that record retries, the only ones kept across restarts
+const RETRY_LOG: &str = "[retry]";
+
+pub(crate) fn now() -> i64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or_default()
+}
+
 #[derive(Clone, Serialize)]
 pub struct DownloadProgressPayload {
     pub id: String,
     pub progress: f64,
-    pub status: String,
+    pub status: JobStatus,
     pub detailed_status: Option<String>,
     pub log: Option<String>,
 }
@@ -25,6 +38,175 @@ pub struct MetadataPayload {
     pub album: Option<String>,
     pub thumbnail: Option<String>,
     pub duration: Option<f64>,
+    #[serde(default)]
+    pub version: Option<String>,
+    #[serde(default)]
+    pub remixer: Option<String>,
+    #[serde(default)]
+    pub featured_artists: Option<String>, // comma separated
+    /// Section of the source to download in seconds, either end may be open
+    #[serde(default)]
+    pub clip_start: Option<f64>,
+    #[serde(default)]
+    pub clip_end: Option<f64>,
+    /// Tracks of the video found in its chapters or description
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub chapters: Vec<Chapter>,
+    #[serde(default)]
+    pub track_number: Option<i64>,
+}
+
+impl MetadataPayload {
+    /// Limits the download to `start..end` seconds of the source and
+    /// shortens the duration to match.
+    pub fn set_clip(&mut self, start: Option<f64>, end: Option<f64>) -> Result<(), String> {
+        if start.is_none() && end.is_none() {
+            return Ok(());
+        }
+        let valid = |t: Option<f64>| t.is_none_or(|t| t.is_finite() && t >= 0.0);
+        if !valid(start) || !valid(end) {
+            return Err("Clip times must be a positive number of seconds".into());
+        }
+        let start = start.unwrap_or(0.0);
+        if end.is_some_and(|end| end <= start) {
+            return Err("The clip must end after it starts".into());
+        }
+        if self.duration.is_some_and(|duration| start >= duration) {
+            return Err("The clip starts after the end of the video".into());
+        }
+
+        self.clip_start = Some(start).filter(|s| *s > 0.0);
+        self.clip_end = end;
+        if let Some(duration) = self.duration {
+            self.duration = Some(end.unwrap_or(duration).min(duration) - start);
+        }
+        Ok(())
+    }
+
+    /// Value for yt-dlp's `--download-sections`, None for the whole source.
+    pub fn download_section(&self) -> Option<String> {
+        if self.clip_start.is_none() && self.clip_end.is_none() {
+            return None;
+        }
+        let end = self
+            .clip_end
+            .map_or("inf".to_string(), |end| end.to_string());
+        Some(format!("*{}-{}", self.clip_start.unwrap_or(0.0), end))
+    }
+}
+
+/// Optional settings of a download added with `add_to_queue`.
+#[derive(Debug, Default, Deserialize)]
+pub struct QueueOptions {
+    /// Quality profile name, the configured default when None
+    pub profile: Option<String>,
+    /// Seconds into the source to start the clip at
+    pub start: Option<f64>,
+    /// Seconds into the source to end the clip at
+    pub end: Option<f64>,
+    /// Queue one song per chapter instead of the whole video
+    #[serde(default)]
+    pub split_chapters: bool,
+}
+
+/// Song id for a clip of the source `id`, so several sections of one mix
+/// don't replace each other in the library.
+pub fn clip_id(id: &str, start: Option<f64>, end: Option<f64>) -> String {
+    if start.is_none() && end.is_none() {
+        return id.to_string();
+    }
+    let end = end.map_or("end".to_string(), |end| end.to_string());
+    format!("{}@{}-{}", id, start.unwrap_or(0.0), end)
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum JobStatus {
+    Queued,
+    /// Resolving the media before any bytes are downloaded
+    Fetching,
+    Downloading,
+    /// Extracting audio, tagging and embedding artwork
+    PostProcessing,
+    Completed,
+    Failed {
+        reason: String,
+    },
+    Cancelled,
+    /// Stopped by the user with its partial download kept for resuming
+    Paused,
+}
+
+impl JobStatus {
+    pub fn name(&self) -> &'static str {
+        match self {
+            JobStatus::Queued => "queued",
+            JobStatus::Fetching => "fetching",
+            JobStatus::Downloading => "downloading",
+            JobStatus::PostProcessing => "post_processing",
+            JobStatus::Completed => "completed",
+            JobStatus::Failed { .. } => "failed",
+            JobStatus::Cancelled => "cancelled",
+            JobStatus::Paused => "paused",
+        }
+    }
+
+    /// Rebuilds a status from its stored name, including the names used
+    /// before statuses were typed.
+    pub fn from_name(name: &str, reason: Option<String>) -> Self {
+        match name {
+            "queued" => JobStatus::Queued,
+            "fetching" | "pending" => JobStatus::Fetching,
+            "downloading" => JobStatus::Downloading,
+            "post_processing" => JobStatus::PostProcessing,
+            "completed" => JobStatus::Completed,
+            "cancelled" => JobStatus::Cancelled,
+            "paused" => JobStatus::Paused,
+            _ => JobStatus::Failed {
+                reason: reason.unwrap_or_else(|| "Unknown error".to_string()),
+            },
+        }
+    }
+
+    /// Whether a worker is running the job.
+    pub fn is_active(&self) -> bool {
+        matches!(
+            self,
+            JobStatus::Fetching | JobStatus::Downloading | JobStatus::PostProcessing
+        )
+    }
+
+    pub fn is_finished(&self) -> bool {
+        matches!(
+            self,
+            JobStatus::Completed | JobStatus::Failed { .. } | JobStatus::Cancelled
+        )
+    }
+
+    pub fn can_transition_to(&self, next: &JobStatus) -> bool {
+        use JobStatus::*;
+        match (self, next) {
+            (Queued, Fetching | Cancelled) => true,
+            (Fetching, Downloading) => true,
+            (Fetching | Downloading, PostProcessing) => true,
+            (Fetching | Downloading | PostProcessing, Completed | Failed { .. } | Cancelled) => {
+                true
+            }
+            // Active jobs go back to the queue when the app exits mid-download
+            (Fetching | Downloading | PostProcessing, Queued) => true,
+            (Completed | Failed { .. } | Cancelled, Queued) => true,
+            // Post-processing works on the finished download, so it can't be paused
+            (Queued | Fetching | Downloading, Paused) => true,
+            (Paused, Queued | Cancelled) => true,
+            _ => false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StatusChange {
+    pub status: JobStatus,
+    pub at: i64, // unix timestamp
 }
 
 #[derive(Clone, Serialize, Deserialize)]
@@ -32,9 +214,261 @@ pub struct DownloadJob {
     pub id: String,
     pub title: String,
     pub progress: f64,
-    pub status: String, // "queued" | "pending" | "downloading" | "completed" | "error"
+    pub status: JobStatus,
     pub detailed_status: Option<String>,
     pub url: String,
     pub metadata: MetadataPayload,
     pub logs: Vec<String>,
+    #[serde(default)]
+    pub attempts: u32,
+    /// Every status the job went through, oldest first
+    #[serde(default)]
+    pub transitions: Vec<StatusChange>,
+    /// Unix timestamp before which a queued retry is not started
+    #[serde(default)]
+    pub retry_at: Option<i64>,
+    /// Queued jobs with a higher priority start first, ties in the order they were added
+    #[serde(default)]
+    pub priority: i64,
+    /// Name of the quality profile, None for the configured default
+    #[serde(default)]
+    pub profile: Option<String>,
+    /// Path under `Songs/` without extension that yt-dlp writes to, once picked
+    #[serde(default)]
+    pub output: Option<String>,
+}
+
+impl DownloadJob {
+    pub fn new(id: String, url: String, metadata: MetadataPayload) -> Self {
+        DownloadJob {
+            id,
+            title: format!("{} - {}", metadata.artist, metadata.title),
+            progress: 0.0,
+            status: JobStatus::Queued,
+            detailed_status: None,
+            url,
+            metadata,
+            logs: Vec::new(),
+            attempts: 0,
+            transitions: vec![StatusChange {
+                status: JobStatus::Queued,
+                at: now(),
+            }],
+            retry_at: None,
+            priority: 0,
+            profile: None,
+            output: None,
+        }
+    }
+
+    /// Moves the job to `next`, recording when it happened. Staying in the
+    /// same status is a no-op.
+    pub fn transition(&mut self, next: JobStatus) -> Result<(), String> {
+        if self.status == next {
+            return Ok(());
+        }
+        if !self.status.can_transition_to(&next) {
+            return Err(format!(
+                "Download {} cannot go from {} to {}",
+                self.id,
+                self.status.name(),
+                next.name()
+            ));
+        }
+        self.transitions.push(StatusChange {
+            status: next.clone(),
+            at: now(),
+        });
+        self.status = next;
+        Ok(())
+    }
+
+    pub fn to_record(&self) -> Result<DownloadRecord, serde_json::Error> {
+        Ok(DownloadRecord {
+            id: self.id.clone(),
+            url: self.url.clone(),
+            title: self.title.clone(),
+            status: self.status.name().to_string(),
+            progress: self.progress,
+            detailed_status: self.detailed_status.clone(),
+            metadata: serde_json::to_string(&self.metadata)?,
+            attempts: self.attempts as i64,
+            error: match self.status {
+                JobStatus::Failed { ref reason } => Some(reason.clone()),
+                _ => None,
+            },
+            created_at: 0,
+            updated_at: 0,
+            transitions: Some(serde_json::to_string(&self.transitions)?),
+            priority: self.priority,
+            profile: self.profile.clone(),
+            // Output of yt-dlp is only useful while it runs, the retry history is kept
+            logs: Some(serde_json::to_string(
+                &self
+                    .logs
+                    .iter()
+                    .filter(|line| line.starts_with(RETRY_LOG))
+                    .collect::<Vec<_>>(),
+            )?),
+            output: self.output.clone(),
+        })
+    }
+
+    pub fn from_record(record: DownloadRecord) -> Result<Self, serde_json::Error> {
+        let transitions = match record.transitions {
+            Some(ref json) => serde_json::from_str(json)?,
+            None => Vec::new(),
+        };
+        let logs = match record.logs {
+            Some(ref json) => serde_json::from_str(json)?,
+            None => Vec::new(),
+        };
+        Ok(DownloadJob {
+            metadata: serde_json::from_str(&record.metadata)?,
+            status: JobStatus::from_name(&record.status, record.error),
+            id: record.id,
+            title: record.title,
+            progress: record.progress,
+            detailed_status: record.detailed_status,
+            url: record.url,
+            logs,
+            attempts: record.attempts.max(0) as u32,
+            transitions,
+            retry_at: None,
+            priority: record.priority,
+            profile: record.profile,
+            output: record.output,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn job() -> DownloadJob {
+        let metadata = MetadataPayload {
+            id: "1".to_string(),
+            url: "https://example.com".to_string(),
+            title: "Title".to_string(),
+            artist: "Artist".to_string(),
+            album: None,
+            thumbnail: None,
+            duration: None,
+            version: None,
+            remixer: None,
+            featured_artists: None,
+            clip_start: None,
+            clip_end: None,
+            chapters: Vec::new(),
+            track_number: None,
+        };
+        DownloadJob::new("1".to_string(), metadata.url.clone(), metadata)
+    }
+
+    #[test]
+    fn test_job_transitions() {
+        let mut job = job();
+        job.transition(JobStatus::Fetching).unwrap();
+        job.transition(JobStatus::Downloading).unwrap();
+        job.transition(JobStatus::Downloading).unwrap();
+        job.transition(JobStatus::PostProcessing).unwrap();
+        job.transition(JobStatus::Failed {
+            reason: "boom".to_string(),
+        })
+        .unwrap();
+
+        // A failure must never be reported as a success
+        assert!(job.transition(JobStatus::Completed).is_err());
+        assert!(job.transition(JobStatus::Downloading).is_err());
+        assert_eq!(job.status.name(), "failed");
+
+        let names: Vec<_> = job.transitions.iter().map(|t| t.status.name()).collect();
+        assert_eq!(
+            names,
+            [
+                "queued",
+                "fetching",
+                "downloading",
+                "post_processing",
+                "failed"
+            ]
+        );
+
+        job.transition(JobStatus::Queued).unwrap();
+        assert!(job.transition(JobStatus::Completed).is_err());
+
+        job.transition(JobStatus::Fetching).unwrap();
+        job.transition(JobStatus::Downloading).unwrap();
+        job.transition(JobStatus::Paused).unwrap();
+        assert!(job.transition(JobStatus::Downloading).is_err());
+        job.transition(JobStatus::Queued).unwrap();
+        job.transition(JobStatus::Fetching).unwrap();
+        job.transition(JobStatus::PostProcessing).unwrap();
+        assert!(job.transition(JobStatus::Paused).is_err());
+    }
+
+    #[test]
+    fn test_job_record_roundtrip() {
+        let mut job = job();
+        job.transition(JobStatus::Fetching).unwrap();
+        job.transition(JobStatus::Failed {
+            reason: "boom".to_string(),
+        })
+        .unwrap();
+        job.output = Some("Artist/Song".to_string());
+        job.logs.push("[download] 50.0% of 3.00MiB".to_string());
+        job.logs
+            .push("[retry] Attempt 1 of 3 failed: boom. Retrying in 3s".to_string());
+
+        let record = job.to_record().unwrap();
+        assert_eq!(record.status, "failed");
+        assert_eq!(record.error.as_deref(), Some("boom"));
+
+        let restored = DownloadJob::from_record(record).unwrap();
+        assert_eq!(
+            restored.status,
+            JobStatus::Failed {
+                reason: "boom".to_string()
+            }
+        );
+        assert_eq!(restored.transitions.len(), 3);
+        assert_eq!(restored.output.as_deref(), Some("Artist/Song"));
+        assert_eq!(
+            restored.logs,
+            ["[retry] Attempt 1 of 3 failed: boom. Retrying in 3s"]
+        );
+        assert_eq!(JobStatus::from_name("pending", None), JobStatus::Fetching);
+    }
+
+    #[test]
+    fn test_clip() {
+        let mut metadata = job().metadata;
+        metadata.duration = Some(3600.0);
+        assert_eq!(metadata.download_section(), None);
+
+        assert!(metadata.set_clip(Some(600.0), Some(300.0)).is_err());
+        assert!(metadata.set_clip(Some(-1.0), None).is_err());
+        assert!(metadata.set_clip(Some(4000.0), None).is_err());
+        assert!(metadata.set_clip(Some(f64::NAN), None).is_err());
+
+        metadata.set_clip(Some(93.5), Some(420.0)).unwrap();
+        assert_eq!(metadata.download_section().as_deref(), Some("*93.5-420"));
+        assert_eq!(metadata.duration, Some(326.5));
+        assert_eq!(
+            clip_id("abc", metadata.clip_start, metadata.clip_end),
+            "abc@93.5-420"
+        );
+
+        let mut metadata = job().metadata;
+        metadata.set_clip(Some(0.0), Some(60.0)).unwrap();
+        assert_eq!(metadata.clip_start, None);
+        assert_eq!(metadata.download_section().as_deref(), Some("*0-60"));
+
+        let mut metadata = job().metadata;
+        metadata.set_clip(Some(3000.0), None).unwrap();
+        assert_eq!(metadata.download_section().as_deref(), Some("*3000-inf"));
+        assert_eq!(clip_id("abc", Some(3000.0), None), "abc@3000-end");
+        assert_eq!(clip_id("abc", None, None), "abc");
+    }
 }
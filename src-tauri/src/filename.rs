@@ -0,0 +1,373 @@
+//! Builds library file names from the user's template.
+
+use std::path::Path;
+
+use crate::db::entities::Song;
+use crate::db::Database;
+
+/// Tokens a filename template may contain.
+pub const TOKENS: [&str; 5] = ["artist", "title", "album", "source_id", "bpm"];
+
+/// Template matching the names Cue has always given downloads.
+pub const DEFAULT_TEMPLATE: &str = "{title}-{source_id}";
+
+/// Longest component in UTF-16 units. NTFS and FAT32 allow 255, the rest is
+/// left for the extension and a disambiguating suffix.
+const MAX_COMPONENT_LEN: usize = 180;
+
+/// Names Windows reserves for devices, with or without an extension.
+const RESERVED_NAMES: [&str; 22] = [
+    "CON", "PRN", "AUX", "NUL", "COM1", "COM2", "COM3", "COM4", "COM5", "COM6", "COM7", "COM8",
+    "COM9", "LPT1", "LPT2", "LPT3", "LPT4", "LPT5", "LPT6", "LPT7", "LPT8", "LPT9",
+];
+
+/// Values substituted for the template tokens.
+#[derive(Debug, Default, Clone)]
+pub struct TemplateFields {
+    pub artist: String,
+    pub title: String,
+    pub album: Option<String>,
+    pub source_id: String,
+    /// Songs don't carry a BPM yet, so `{bpm}` renders empty until they do
+    pub bpm: Option<f64>,
+}
+
+impl TemplateFields {
+    pub fn from_song(song: &Song) -> Self {
+        let title = match song.version {
+            Some(ref version) => format!("{} ({})", song.title, version),
+            None => song.title.clone(),
+        };
+        TemplateFields {
+            artist: song.full_artist(),
+            title,
+            album: song.album.clone(),
+            source_id: song.id.clone(),
+            bpm: None,
+        }
+    }
+
+    fn value(&self, token: &str) -> String {
+        match token {
+            "artist" => self.artist.clone(),
+            "title" => self.title.clone(),
+            "album" => self.album.clone().unwrap_or_default(),
+            "source_id" => self.source_id.clone(),
+            "bpm" => self.bpm.map(|b| b.round().to_string()).unwrap_or_default(),
+            _ => String::new(),
+        }
+    }
+}
+
+/// Makes one path component safe on NTFS and FAT32 while keeping Unicode
+/// letters intact.
+pub fn sanitize(component: &str) -> String {
+    let replaced: String = component
+        .chars()
+        .filter_map(|c| match c {
+            '/' | '\\' | '|' | ':' => Some('-'),
+            '"' => Some('\''),
+            '<' | '>' | '?' | '*' => None,
+            c if c.is_control() => None,
+            c => Some(c),
+        })
+        .collect();
+
+    let collapsed = replaced.split_whitespace().collect::<Vec<_>>().join(" ");
+    // Windows drops trailing dots and spaces, and leading dots hide files elsewhere
+    let trimmed = collapsed
+        .trim_start_matches(['.', ' '])
+        .trim_end_matches(['.', ' ']);
+
+    let mut name = String::new();
+    let mut len = 0;
+    for c in trimmed.chars() {
+        len += c.len_utf16();
+        if len > MAX_COMPONENT_LEN {
+            break;
+        }
+        name.push(c);
+    }
+    let name = name.trim_end_matches(['.', ' ']).to_string();
+
+    let stem = name.split('.').next().unwrap_or_default();
+    if RESERVED_NAMES.iter().any(|r| r.eq_ignore_ascii_case(stem)) {
+        return format!("{}_", name);
+    }
+    if name.is_empty() {
+        return "Unknown".to_string();
+    }
+    name
+}
+
+/// Checks that a template only uses known tokens and names a file.
+pub fn validate(template: &str) -> Result<(), String> {
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        let end = rest[start..]
+            .find('}')
+            .ok_or_else(|| format!("Unclosed token in template \"{}\"", template))?;
+        let token = &rest[start + 1..start + end];
+        if !TOKENS.contains(&token) {
+            return Err(format!(
+                "Unknown token {{{}}}, expected one of: {}",
+                token,
+                TOKENS.map(|t| format!("{{{}}}", t)).join(", ")
+            ));
+        }
+        rest = &rest[start + end + 1..];
+    }
+
+    if template.trim_end_matches('/').trim().is_empty() {
+        return Err("Filename template cannot be empty".to_string());
+    }
+    Ok(())
+}
+
+fn render_component(component: &str, fields: &TemplateFields) -> String {
+    let mut out = String::new();
+    let mut rest = component;
+    while let Some(start) = rest.find('{') {
+        out.push_str(&rest[..start]);
+        match rest[start..].find('}') {
+            Some(end) => {
+                out.push_str(&fields.value(&rest[start + 1..start + end]));
+                rest = &rest[start + end + 1..];
+            }
+            None => {
+                out.push_str(&rest[start..]);
+                rest = "";
+            }
+        }
+    }
+    out.push_str(rest);
+
+    // Tokens with no value leave separators behind, e.g. "{bpm} - {title}"
+    sanitize(out.trim_matches(|c: char| c == '-' || c == '_' || c.is_whitespace()))
+}
+
+/// Renders `template` into a relative path under `Songs/`, without extension,
+/// using `/` between folders.
+pub fn render(template: &str, fields: &TemplateFields) -> String {
+    template
+        .trim_end_matches('/')
+        .split(['/', '\\'])
+        .filter(|c| !c.trim().is_empty())
+        .map(|c| render_component(c, fields))
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// Appends `ext` to `rendered`, disambiguating when the name already belongs
+/// to another song or to a file the library doesn't know about.
+pub async fn unique(
+    db: &Database,
+    rendered: &str,
+    song_id: &str,
+    ext: &str,
+) -> Result<String, sqlx::Error> {
+    let stem = unique_stem(db, rendered, song_id, &[ext], &[]).await?;
+    Ok(format!("{}.{}", stem, ext))
+}
+
+/// Like `unique`, for a file whose extension isn't known yet but will be one
+/// of `exts`, and that must not reuse a stem in `reserved`. Returns the name
+/// without an extension.
+pub async fn unique_stem(
+    db: &Database,
+    rendered: &str,
+    song_id: &str,
+    exts: &[&str],
+    reserved: &[&str],
+) -> Result<String, sqlx::Error> {
+    let songs_dir = Path::new(&db.library_path).join("Songs");
+    let suffixes = std::iter::once(String::new())
+        .chain(std::iter::once(format!(" [{}]", sanitize(song_id))))
+        .chain((2..).map(|n| format!(" ({})", n)));
+
+    'suffixes: for suffix in suffixes {
+        let stem = format!("{}{}", rendered, suffix);
+        if reserved.contains(&stem.as_str()) {
+            continue;
+        }
+        for ext in exts {
+            let candidate = format!("{}.{}", stem, ext);
+            let taken = match db.get_song_by_filename(&candidate).await? {
+                Some(owner) => owner.id != song_id,
+                None => songs_dir.join(&candidate).exists(),
+            };
+            if taken {
+                continue 'suffixes;
+            }
+        }
+        return Ok(stem);
+    }
+    unreachable!("the suffix iterator is infinite")
+}
+
+/// Removes `dir` and its parents up to `root` while they are empty.
+fn remove_empty_dirs(root: &Path, dir: &Path) {
+    let mut dir = dir.to_path_buf();
+    while dir.starts_with(root) && dir != root {
+        if std::fs::remove_dir(&dir).is_err() {
+            break;
+        }
+        if !dir.pop() {
+            break;
+        }
+    }
+}
+
+/// Moves every song's file to the path `template` gives it, returning how
+/// many were renamed. Songs whose file is missing are left alone.
+pub async fn rename_all(db: &Database, template: &str) -> anyhow::Result<usize> {
+    validate(template).map_err(anyhow::Error::msg)?;
+    let songs_dir = Path::new(&db.library_path).join("Songs");
+
+    let mut renamed = 0;
+    for song in db.get_songs().await? {
+        let from = songs_dir.join(&song.filename);
+        if !from.exists() {
+            continue;
+        }
+
+        let ext = from
+            .extension()
+            .map(|e| e.to_string_lossy().to_string())
+            .unwrap_or_else(|| "mp3".to_string());
+        let rendered = render(template, &TemplateFields::from_song(&song));
+        let target = unique(db, &rendered, &song.id, &ext).await?;
+        if target == song.filename {
+            continue;
+        }
+
+        let to = songs_dir.join(&target);
+        if let Some(parent) = to.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        // rename fails across filesystems, fall back to copy + delete
+        if std::fs::rename(&from, &to).is_err() {
+            std::fs::copy(&from, &to)?;
+            std::fs::remove_file(&from)?;
+        }
+
+        db.update_song_filename(&song.id, &target).await?;
+        if let Some(parent) = from.parent() {
+            remove_empty_dirs(&songs_dir, parent);
+        }
+        renamed += 1;
+    }
+    Ok(renamed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fields() -> TemplateFields {
+        TemplateFields {
+            artist: "Dynoro".to_string(),
+            title: "In My Mind".to_string(),
+            album: None,
+            source_id: "abc123".to_string(),
+            bpm: Some(125.6),
+        }
+    }
+
+    #[test]
+    fn test_sanitize() {
+        let cases = [
+            ("In My Mind", "In My Mind"),
+            ("夜に駆ける", "夜に駆ける"),
+            ("Кино - Группа крови", "Кино - Группа крови"),
+            ("Beyoncé", "Beyoncé"),
+            ("AC/DC", "AC-DC"),
+            ("Track: Part 1", "Track- Part 1"),
+            ("What?", "What"),
+            ("Say \"Hi\"", "Say 'Hi'"),
+            ("<Intro>", "Intro"),
+            ("Tab\there", "Tabhere"),
+            ("  lots   of   space  ", "lots of space"),
+            ("Trailing dots...", "Trailing dots"),
+            ("...Hidden", "Hidden"),
+            ("..", "Unknown"),
+            ("", "Unknown"),
+            ("CON", "CON_"),
+            ("nul.mp3", "nul.mp3_"),
+            ("Console", "Console"),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(sanitize(input), expected, "sanitize({:?})", input);
+        }
+
+        let long = "あ".repeat(300);
+        assert_eq!(sanitize(&long).chars().count(), MAX_COMPONENT_LEN);
+        let emoji = "🎵".repeat(100);
+        assert_eq!(sanitize(&emoji).chars().count(), MAX_COMPONENT_LEN / 2);
+    }
+
+    #[test]
+    fn test_render() {
+        let cases = [
+            (DEFAULT_TEMPLATE, "In My Mind-abc123"),
+            ("{artist} - {title}", "Dynoro - In My Mind"),
+            ("{artist}/{album}/{title}", "Dynoro/Unknown/In My Mind"),
+            ("{artist}/{title}/", "Dynoro/In My Mind"),
+            ("{bpm} - {title}", "126 - In My Mind"),
+            ("{album} - {title}", "In My Mind"),
+            ("{unknown}{title}", "In My Mind"),
+        ];
+        for (template, expected) in cases {
+            assert_eq!(
+                render(template, &fields()),
+                expected,
+                "render({:?})",
+                template
+            );
+        }
+
+        let slashed = TemplateFields {
+            artist: "AC/DC".to_string(),
+            ..fields()
+        };
+        assert_eq!(render("{artist}/{title}", &slashed), "AC-DC/In My Mind");
+
+        let no_bpm = TemplateFields {
+            bpm: None,
+            ..fields()
+        };
+        assert_eq!(render("{bpm} - {title}", &no_bpm), "In My Mind");
+    }
+
+    #[test]
+    fn test_validate() {
+        assert!(validate(DEFAULT_TEMPLATE).is_ok());
+        assert!(validate("{artist}/{album}/{artist} - {title}").is_ok());
+        assert!(validate("{bpm} - {title}").is_ok());
+        assert!(validate("{artist} - {name}").is_err());
+        assert!(validate("{artist").is_err());
+        assert!(validate("/").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_unique_stem() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(dir.path().join("Songs")).unwrap();
+        std::fs::write(dir.path().join("Songs").join("Taken.mp3"), b"audio").unwrap();
+        let db = Database::open(&dir.path().to_string_lossy()).await.unwrap();
+
+        let stem = |rendered: &'static str, reserved: &'static [&'static str]| {
+            let db = db.clone();
+            async move {
+                unique_stem(&db, rendered, "abc", &["mp3"], reserved)
+                    .await
+                    .unwrap()
+            }
+        };
+        assert_eq!(stem("Free", &[]).await, "Free");
+        assert_eq!(stem("Taken", &[]).await, "Taken [abc]");
+        // Held by another running download that hasn't written its file yet
+        assert_eq!(stem("Free", &["Free"]).await, "Free [abc]");
+        assert_eq!(stem("Free", &["Free", "Free [abc]"]).await, "Free (2)");
+    }
+}
@@ -0,0 +1,181 @@
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::fs::File;
+use std::path::Path;
+use std::time::UNIX_EPOCH;
+
+use crate::db::entities::Song;
+use crate::db::Database;
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum VerifyStatus {
+    /// Content matches the stored hash
+    Ok,
+    /// Content differs from the stored hash
+    Changed,
+    /// File is not in the library folder
+    Missing,
+    /// No hash was stored yet, the current one has been recorded
+    Hashed,
+}
+
+#[derive(Clone, Serialize)]
+pub struct VerifyResult {
+    pub song_id: String,
+    pub status: VerifyStatus,
+}
+
+/// SHA-256 of the file's content as a lowercase hex string.
+pub fn hash_file(path: &Path) -> std::io::Result<String> {
+    let mut hasher = Sha256::new();
+    std::io::copy(&mut File::open(path)?, &mut hasher)?;
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+/// Modification time of the file in unix seconds.
+pub fn file_mtime(path: &Path) -> Option<i64> {
+    std::fs::metadata(path)
+        .and_then(|m| m.modified())
+        .ok()
+        .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+        .map(|d| d.as_secs() as i64)
+}
+
+/// Hashes on the blocking pool so large files don't stall the async runtime.
+pub async fn hash_file_async(path: &Path) -> anyhow::Result<(String, Option<i64>)> {
+    let path = path.to_path_buf();
+    let result = tauri::async_runtime::spawn_blocking(move || {
+        hash_file(&path).map(|hash| (hash, file_mtime(&path)))
+    })
+    .await??;
+    Ok(result)
+}
+
+fn song_path(db: &Database, song: &Song) -> std::path::PathBuf {
+    Path::new(&db.library_path)
+        .join("Songs")
+        .join(&song.filename)
+}
+
+/// Records hashes for songs that don't have one yet, e.g. those added before hashing existed.
+/// A file that can't be hashed is skipped so it doesn't hold up the rest.
+pub async fn backfill(db: &Database) -> anyhow::Result<usize> {
+    let mut hashed = 0;
+    for song in db.get_unhashed_songs().await? {
+        let path = song_path(db, &song);
+        if !path.exists() {
+            continue;
+        }
+
+        let result = match hash_file_async(&path).await {
+            Ok((hash, mtime)) => db
+                .update_song_hash(&song.id, &hash, mtime)
+                .await
+                .map_err(anyhow::Error::from),
+            Err(e) => Err(e),
+        };
+        match result {
+            Ok(()) => hashed += 1,
+            Err(e) => eprintln!("Warning: Failed to hash {}: {}", song.filename, e),
+        }
+    }
+    Ok(hashed)
+}
+
+/// Runs [`backfill`] in the background for a library that was just opened.
+pub fn spawn_backfill(db: &Database) {
+    let db = db.clone();
+    tauri::async_runtime::spawn(async move {
+        if let Err(e) = backfill(&db).await {
+            eprintln!("Warning: Failed to back-fill song hashes: {}", e);
+        }
+    });
+}
+
+/// Re-hashes a song's file and compares it with the stored hash.
+pub async fn verify_song(db: &Database, song: &Song) -> anyhow::Result<VerifyStatus> {
+    let path = song_path(db, song);
+    if !path.exists() {
+        return Ok(VerifyStatus::Missing);
+    }
+
+    let (hash, mtime) = hash_file_async(&path).await?;
+    match song.content_hash {
+        Some(ref stored) if *stored == hash => Ok(VerifyStatus::Ok),
+        Some(_) => Ok(VerifyStatus::Changed),
+        None => {
+            db.update_song_hash(&song.id, &hash, mtime).await?;
+            Ok(VerifyStatus::Hashed)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_verify_song() {
+        let dir = tempfile::tempdir().unwrap();
+        let songs = dir.path().join("Songs");
+        std::fs::create_dir_all(&songs).unwrap();
+        std::fs::write(songs.join("song.mp3"), b"audio").unwrap();
+
+        let db = Database::open(&dir.path().to_string_lossy()).await.unwrap();
+        let mut song = Song {
+            id: "1".to_string(),
+            title: "Song".to_string(),
+            artist: "Artist".to_string(),
+            filename: "song.mp3".to_string(),
+            ..Default::default()
+        };
+        db.add_song(&song).await.unwrap();
+
+        // The first check records the hash, later ones compare against it
+        let status = verify_song(&db, &song).await.unwrap();
+        assert!(matches!(status, VerifyStatus::Hashed));
+        song = db.get_song_by_id("1").await.unwrap().unwrap();
+        assert_eq!(
+            song.content_hash.as_deref(),
+            Some(hash_file(&songs.join("song.mp3")).unwrap().as_str())
+        );
+        let status = verify_song(&db, &song).await.unwrap();
+        assert!(matches!(status, VerifyStatus::Ok));
+
+        std::fs::write(songs.join("song.mp3"), b"corrupted").unwrap();
+        let status = verify_song(&db, &song).await.unwrap();
+        assert!(matches!(status, VerifyStatus::Changed));
+
+        std::fs::remove_file(songs.join("song.mp3")).unwrap();
+        let status = verify_song(&db, &song).await.unwrap();
+        assert!(matches!(status, VerifyStatus::Missing));
+    }
+
+    #[tokio::test]
+    async fn test_backfill_skips_unreadable() {
+        let dir = tempfile::tempdir().unwrap();
+        let songs = dir.path().join("Songs");
+        // A directory where the file should be can't be read
+        std::fs::create_dir_all(songs.join("broken.mp3")).unwrap();
+        std::fs::write(songs.join("song.mp3"), b"audio").unwrap();
+
+        let db = Database::open(&dir.path().to_string_lossy()).await.unwrap();
+        for (id, filename) in [("1", "broken.mp3"), ("2", "song.mp3")] {
+            let song = Song {
+                id: id.to_string(),
+                title: "Song".to_string(),
+                artist: "Artist".to_string(),
+                filename: filename.to_string(),
+                ..Default::default()
+            };
+            db.add_song(&song).await.unwrap();
+        }
+
+        assert_eq!(backfill(&db).await.unwrap(), 1);
+        let song = db.get_song_by_id("2").await.unwrap().unwrap();
+        assert!(song.content_hash.is_some());
+        let broken = db.get_song_by_id("1").await.unwrap().unwrap();
+        assert_eq!(broken.content_hash, None);
+    }
+}
@@ -0,0 +1,376 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Emitter, Manager};
+use tokio::process::Command;
+
+use crate::bundler;
+use crate::db::entities::Song;
+use crate::db::Database;
+use crate::download::DownloadManager;
+use crate::hashing;
+
+pub const AUDIO_EXTENSIONS: [&str; 9] = [
+    "mp3", "m4a", "aac", "opus", "ogg", "flac", "wav", "aiff", "aif",
+];
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Finding {
+    MissingFile {
+        song_id: String,
+        filename: String,
+    },
+    OrphanFile {
+        filename: String,
+    },
+    CorruptFile {
+        song_id: String,
+        filename: String,
+        error: String,
+    },
+    DuplicateFilename {
+        filename: String,
+        song_ids: Vec<String>,
+    },
+    HashChanged {
+        song_id: String,
+        filename: String,
+        /// Whether the modification time changed too, i.e. the file was edited on purpose
+        modified: bool,
+    },
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum Fix {
+    /// Queue the song for download again from its source URL
+    Redownload { song_id: String },
+    /// Move the song to the trash
+    TrashSong { song_id: String },
+    /// Add a library entry for a file that has none
+    ImportFile { filename: String },
+    /// Move library entries to the trash, leaving the file they share with another entry in place
+    TrashEntries { song_ids: Vec<String> },
+    /// Record the file's current hash as the expected one
+    AcceptHash { song_id: String },
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Issue {
+    pub finding: Finding,
+    pub fix: Fix,
+}
+
+#[derive(Clone, Serialize)]
+pub struct RepairReport {
+    pub applied: usize,
+    pub errors: Vec<String>,
+}
+
+#[derive(Clone, Serialize)]
+pub struct IntegrityProgressPayload {
+    pub status: String,
+    pub progress: f64,
+}
+
+pub fn is_audio_file(path: &Path) -> bool {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|e| AUDIO_EXTENSIONS.contains(&e.to_ascii_lowercase().as_str()))
+}
+
+fn collect_audio_files(root: &Path, dir: &Path, files: &mut Vec<String>) -> std::io::Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            collect_audio_files(root, &path, files)?;
+        } else if is_audio_file(&path) {
+            if let Ok(rel) = path.strip_prefix(root) {
+                files.push(rel.to_string_lossy().replace('\\', "/"));
+            }
+        }
+    }
+    Ok(())
+}
+
+fn reacquire_fix(song: &Song) -> Fix {
+    if song.source_url.is_some() {
+        Fix::Redownload {
+            song_id: song.id.clone(),
+        }
+    } else {
+        Fix::TrashSong {
+            song_id: song.id.clone(),
+        }
+    }
+}
+
+/// Decodes every audio frame with ffprobe, returning the first error it reports.
+async fn probe_file(ffprobe: &Path, path: &Path) -> Option<String> {
+    let mut cmd = Command::new(ffprobe);
+    cmd.args(["-v", "error", "-count_frames", "-select_streams", "a:0"])
+        .args(["-show_entries", "stream=nb_read_frames", "-of", "csv=p=0"])
+        .arg(path);
+
+    #[cfg(windows)]
+    cmd.creation_flags(bundler::CREATE_NO_WINDOW);
+
+    let output = match cmd.output().await {
+        Ok(o) => o,
+        Err(e) => return Some(format!("Failed to run ffprobe: {}", e)),
+    };
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    if !output.status.success() || !stderr.trim().is_empty() {
+        return Some(
+            stderr
+                .lines()
+                .next()
+                .unwrap_or("ffprobe could not read the file")
+                .to_string(),
+        );
+    }
+
+    let frames = String::from_utf8_lossy(&output.stdout);
+    match frames.trim().parse::<u64>() {
+        Ok(n) if n > 0 => None,
+        _ => Some("No audio frames could be decoded".to_string()),
+    }
+}
+
+/// Files written next to a song while it is downloaded or tagged, not songs of their own.
+fn is_temp_file(filename: &str) -> bool {
+    filename.contains(".tagging.")
+        || filename.contains(".part-Frag")
+        || filename.ends_with(".part")
+        || filename.ends_with(".ytdl")
+}
+
+/// Scans the library for missing, orphaned, corrupt, changed and duplicated files.
+pub async fn scan(app: &AppHandle, db: &Database) -> anyhow::Result<Vec<Issue>> {
+    let ffprobe = bundler::ffprobe_path(app).ok();
+    let downloading = app.state::<DownloadManager>().reserved_stems().await;
+    scan_with(db, ffprobe.as_deref(), &downloading, &|payload| {
+        let _ = app.emit("library://integrity-progress", payload);
+    })
+    .await
+}
+
+/// `scan`, probing files with `ffprobe` when there is one and reporting progress
+/// to `progress`. Files of running downloads, named after one of `downloading`,
+/// are left alone.
+async fn scan_with(
+    db: &Database,
+    ffprobe: Option<&Path>,
+    downloading: &[String],
+    progress: &(dyn Fn(IntegrityProgressPayload) + Sync),
+) -> anyhow::Result<Vec<Issue>> {
+    let songs_dir = Path::new(&db.library_path).join("Songs");
+    let songs = db.get_songs().await?;
+    let trashed = db.get_trashed_songs().await?;
+
+    let mut issues = Vec::new();
+
+    // Several rows pointing at the same file
+    let mut by_filename: HashMap<&str, Vec<&Song>> = HashMap::new();
+    for song in &songs {
+        by_filename.entry(&song.filename).or_default().push(song);
+    }
+    for (filename, group) in &mut by_filename {
+        if group.len() > 1 {
+            // The entry added first keeps the file
+            group.sort_by(|a, b| a.created_at.cmp(&b.created_at).then(a.id.cmp(&b.id)));
+            issues.push(Issue {
+                finding: Finding::DuplicateFilename {
+                    filename: filename.to_string(),
+                    song_ids: group.iter().map(|s| s.id.clone()).collect(),
+                },
+                fix: Fix::TrashEntries {
+                    song_ids: group.iter().skip(1).map(|s| s.id.clone()).collect(),
+                },
+            });
+        }
+    }
+
+    let total = songs.len().max(1) as f64;
+    for (i, song) in songs.iter().enumerate() {
+        progress(IntegrityProgressPayload {
+            status: format!("Checking {}", song.title),
+            progress: i as f64 / total * 100.0,
+        });
+
+        let path = songs_dir.join(&song.filename);
+        let size = match fs::metadata(&path) {
+            Ok(m) => m.len(),
+            Err(_) => {
+                issues.push(Issue {
+                    finding: Finding::MissingFile {
+                        song_id: song.id.clone(),
+                        filename: song.filename.clone(),
+                    },
+                    fix: reacquire_fix(song),
+                });
+                continue;
+            }
+        };
+
+        let error = if size == 0 {
+            Some("File is empty".to_string())
+        } else if let Some(ffprobe) = ffprobe {
+            probe_file(ffprobe, &path).await
+        } else {
+            None
+        };
+
+        if let Some(error) = error {
+            issues.push(Issue {
+                finding: Finding::CorruptFile {
+                    song_id: song.id.clone(),
+                    filename: song.filename.clone(),
+                    error,
+                },
+                fix: reacquire_fix(song),
+            });
+            continue;
+        }
+
+        if let Some(ref stored) = song.content_hash {
+            let (hash, mtime) = hashing::hash_file_async(&path).await?;
+            if *stored != hash {
+                // Same mtime with different content points at silent corruption
+                let modified = mtime != song.file_mtime;
+                issues.push(Issue {
+                    finding: Finding::HashChanged {
+                        song_id: song.id.clone(),
+                        filename: song.filename.clone(),
+                        modified,
+                    },
+                    fix: if modified {
+                        Fix::AcceptHash {
+                            song_id: song.id.clone(),
+                        }
+                    } else {
+                        reacquire_fix(song)
+                    },
+                });
+            }
+        }
+    }
+
+    // Files on disk that no row (trashed or not) refers to
+    let known: HashSet<&str> = songs
+        .iter()
+        .chain(&trashed)
+        .map(|s| s.filename.as_str())
+        .collect();
+    let mut files = Vec::new();
+    if songs_dir.exists() {
+        collect_audio_files(&songs_dir, &songs_dir, &mut files)?;
+    }
+    let in_flight = |filename: &str| {
+        is_temp_file(filename)
+            || downloading
+                .iter()
+                .any(|stem| filename.starts_with(&format!("{}.", stem)))
+    };
+    for filename in files {
+        if !known.contains(filename.as_str()) && !in_flight(&filename) {
+            issues.push(Issue {
+                finding: Finding::OrphanFile {
+                    filename: filename.clone(),
+                },
+                fix: Fix::ImportFile { filename },
+            });
+        }
+    }
+
+    progress(IntegrityProgressPayload {
+        status: "Done".to_string(),
+        progress: 100.0,
+    });
+
+    Ok(issues)
+}
+
+/// Adds a library entry for a file found in `Songs/` with no row of its own.
+pub async fn import_file(db: &Database, filename: &str) -> anyhow::Result<()> {
+    let path = Path::new(&db.library_path).join("Songs").join(filename);
+    if !path.exists() {
+        return Err(anyhow::anyhow!("File {} not found", filename));
+    }
+
+    let title = PathBuf::from(filename)
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_else(|| filename.to_string());
+
+    let song = Song {
+        id: format!("local:{}", filename),
+        title,
+        artist: "Unknown Artist".to_string(),
+        filename: filename.to_string(),
+        created_at: hashing::file_mtime(&path),
+        ..Default::default()
+    };
+    db.add_song(&song).await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_scan() {
+        let dir = tempfile::tempdir().unwrap();
+        let songs_dir = dir.path().join("Songs");
+        fs::create_dir_all(songs_dir.join("Artist")).unwrap();
+        fs::write(songs_dir.join("present.mp3"), b"audio").unwrap();
+        fs::write(songs_dir.join("Artist").join("orphan.mp3"), b"audio").unwrap();
+        fs::write(songs_dir.join("cover.jpg"), b"image").unwrap();
+        fs::write(songs_dir.join("present.tagging.mp3"), b"audio").unwrap();
+        fs::write(songs_dir.join("Artist").join("Next.m4a"), b"audio").unwrap();
+
+        let db = Database::open(&dir.path().to_string_lossy()).await.unwrap();
+        let song = |id: &str, filename: &str, source_url: Option<&str>| Song {
+            id: id.to_string(),
+            title: id.to_string(),
+            artist: "Artist".to_string(),
+            filename: filename.to_string(),
+            source_url: source_url.map(str::to_string),
+            ..Default::default()
+        };
+        db.add_song(&song("present", "present.mp3", None))
+            .await
+            .unwrap();
+        db.add_song(&song(
+            "missing",
+            "missing.mp3",
+            Some("https://example.com/1"),
+        ))
+        .await
+        .unwrap();
+
+        let issues = scan_with(&db, None, &["Artist/Next".to_string()], &|_| {})
+            .await
+            .unwrap();
+        assert_eq!(issues.len(), 2);
+        assert!(issues.iter().any(|issue| matches!(
+            (&issue.finding, &issue.fix),
+            (Finding::MissingFile { song_id, .. }, Fix::Redownload { .. }) if song_id == "missing"
+        )));
+        assert!(issues.iter().any(|issue| matches!(
+            (&issue.finding, &issue.fix),
+            (Finding::OrphanFile { filename }, Fix::ImportFile { .. }) if filename == "Artist/orphan.mp3"
+        )));
+
+        import_file(&db, "Artist/orphan.mp3").await.unwrap();
+        let issues = scan_with(&db, None, &["Artist/Next".to_string()], &|_| {})
+            .await
+            .unwrap();
+        assert_eq!(issues.len(), 1);
+        assert!(matches!(issues[0].finding, Finding::MissingFile { .. }));
+    }
+}
@@ -1,9 +1,21 @@
+mod artwork;
+mod backup;
 mod bundler;
 mod commands;
 mod config;
 mod db;
 mod download;
+mod filename;
+mod hashing;
+mod integrity;
+mod protocol;
+mod quality;
 mod rekordbox;
+mod relink;
+mod relocate;
+mod stream;
+mod tagging;
+mod trash;
 
 use db::Database;
 use std::sync::Mutex;
@@ -24,18 +36,42 @@ pub fn run() {
         })
         .plugin(tauri_plugin_opener::init())
         .plugin(tauri_plugin_dialog::init())
+        .register_asynchronous_uri_scheme_protocol(artwork::PROTOCOL, |ctx, request, responder| {
+            let app = ctx.app_handle().clone();
+            let path = request.uri().path().to_string();
+            tauri::async_runtime::spawn(async move {
+                responder.respond(artwork::handle_request(&app, &path).await);
+            });
+        })
+        .register_asynchronous_uri_scheme_protocol(stream::PROTOCOL, |ctx, request, responder| {
+            let app = ctx.app_handle().clone();
+            tauri::async_runtime::spawn(async move {
+                responder.respond(stream::handle_request(&app, &request).await);
+            });
+        })
         .invoke_handler(tauri::generate_handler![
             commands::get_config,
             commands::update_config,
             commands::get_songs,
+            commands::get_songs_page,
             commands::search_songs,
             commands::get_metadata,
             commands::remove_song,
+            commands::restore_song,
+            commands::get_trash,
+            commands::empty_trash,
             commands::remove_download,
             commands::add_to_queue,
             commands::get_downloads,
             commands::clear_history,
             commands::clear_queue,
+            commands::retry_failed_downloads,
+            commands::pause_download,
+            commands::resume_download,
+            commands::move_download,
+            commands::reorder_queue,
+            commands::set_queue_paused,
+            commands::is_queue_paused,
             commands::read_file_content,
             commands::initialize_setup,
             commands::get_song_by_id,
@@ -45,6 +81,28 @@ pub fn run() {
             commands::check_missing_songs,
             commands::sync_song,
             commands::update_song_tags,
+            commands::update_song_rating,
+            commands::update_song_colour,
+            commands::update_song_comments,
+            commands::update_song_credits,
+            commands::update_song_play_count,
+            commands::increment_play_count,
+            commands::get_history,
+            commands::undo_last_change,
+            commands::create_backup,
+            commands::list_backups,
+            commands::restore_backup,
+            commands::relocate_library,
+            commands::create_profile,
+            commands::switch_profile,
+            commands::delete_profile,
+            commands::check_library_integrity,
+            commands::repair_library,
+            commands::verify_songs,
+            commands::rehash_songs,
+            commands::relink_missing,
+            commands::rename_songs_to_template,
+            commands::apply_relinks,
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
@@ -60,6 +118,8 @@ async fn init_app(app: &mut tauri::App) -> Result<(), Box<dyn std::error::Error>
     app.manage(cfg_state);
     app.manage(db_state);
     app.manage(download_manager);
+    app.state::<download::DownloadManager>()
+        .start_persistence(app.handle());
 
     // Now try to load config and initialize DB
     let config = match config::load_config() {
@@ -74,15 +134,33 @@ async fn init_app(app: &mut tauri::App) -> Result<(), Box<dyn std::error::Error>
         let is_healthy = bundler::check_bundler_health(app.handle());
 
         if is_healthy {
-            let db_path = format!("sqlite:{}/songs.db", cfg.library_path);
-            match db::init_db(&db_path).await {
-                Ok(pool) => {
+            match Database::open(&cfg.library_path).await {
+                Ok(database) => {
+                    if cfg.trash_retention_days > 0 {
+                        let database = database.clone();
+                        let retention_days = cfg.trash_retention_days;
+                        tauri::async_runtime::spawn(async move {
+                            if let Err(e) = trash::purge_expired(&database, retention_days).await {
+                                eprintln!("Warning: Failed to purge trash: {}", e);
+                            }
+                        });
+                    }
+
+                    hashing::spawn_backfill(&database);
+
+                    {
+                        let database = database.clone();
+                        let handle = app.handle().clone();
+                        tauri::async_runtime::spawn(async move {
+                            if let Err(e) = artwork::backfill(&handle, &database).await {
+                                eprintln!("Warning: Failed to generate artwork: {}", e);
+                            }
+                        });
+                    }
+
                     let state = app.state::<db::DbState>();
                     let mut db_guard = state.lock().unwrap();
-                    *db_guard = Some(Database {
-                        pool,
-                        library_path: cfg.library_path.clone(),
-                    });
+                    *db_guard = Some(database);
                 }
                 Err(e) => eprintln!("Warning: Failed to initialize DB: {}", e),
             }
@@ -92,6 +170,17 @@ async fn init_app(app: &mut tauri::App) -> Result<(), Box<dyn std::error::Error>
                 let mut cfg_guard = state.lock().unwrap();
                 *cfg_guard = Some(cfg.clone());
             }
+
+            // Restored once the config is in place, the scheduler reads its worker limits
+            let database = app.state::<db::DbState>().lock().unwrap().clone();
+            if let Some(database) = database {
+                let manager = app.state::<download::DownloadManager>();
+                if let Err(e) = manager.restore(app.handle(), &database).await {
+                    eprintln!("Warning: Failed to restore download queue: {}", e);
+                }
+            }
+
+            backup::spawn_scheduler(app.handle().clone());
         } else {
             // If NOT healthy, we still manage the config but maybe we shouldn't?
             // The user said: "go back to the setup screen with the values of the config file"
@@ -0,0 +1,61 @@
+use tauri::http::{Response, StatusCode};
+use tauri::{AppHandle, Manager};
+
+use crate::db::{Database, DbState};
+
+/// Escapes every byte outside the URI unreserved set as `%XX`.
+pub fn percent_encode(input: &str) -> String {
+    let mut out = String::with_capacity(input.len());
+    for b in input.bytes() {
+        match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                out.push(b as char)
+            }
+            b => out.push_str(&format!("%{:02X}", b)),
+        }
+    }
+    out
+}
+
+/// Decodes `%XX` escapes, as produced by `encodeURIComponent` in the webview.
+pub fn percent_decode(input: &str) -> String {
+    let bytes = input.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = bytes
+            .get(i + 1..i + 3)
+            .and_then(|h| std::str::from_utf8(h).ok())
+            .and_then(|h| u8::from_str_radix(h, 16).ok());
+        match (bytes[i], hex) {
+            (b'%', Some(b)) => {
+                out.push(b);
+                i += 3;
+            }
+            (b, _) => {
+                out.push(b);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&out).to_string()
+}
+
+pub fn response(status: StatusCode, body: Vec<u8>, content_type: &str) -> Response<Vec<u8>> {
+    Response::builder()
+        .status(status)
+        .header("Content-Type", content_type)
+        .header("Access-Control-Allow-Origin", "*")
+        .body(body)
+        .unwrap_or_default()
+}
+
+pub fn not_found() -> Response<Vec<u8>> {
+    response(StatusCode::NOT_FOUND, Vec::new(), "text/plain")
+}
+
+pub fn database(app: &AppHandle) -> Option<Database> {
+    let state = app.state::<DbState>();
+    let db_guard = state.lock().unwrap();
+    db_guard.clone()
+}
@@ -0,0 +1,176 @@
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use tauri::AppHandle;
+use tokio::process::Command;
+
+use crate::bundler;
+
+/// Profile used by jobs that don't pick one.
+pub const DEFAULT_PROFILE: &str = "MP3 320";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AudioFormat {
+    Mp3,
+    Aiff,
+    Wav,
+    Flac,
+    /// The source's own audio stream, extracted without re-encoding
+    Original,
+}
+
+impl AudioFormat {
+    /// Extension of the finished file, None when it depends on the source.
+    pub fn extension(self) -> Option<&'static str> {
+        match self {
+            AudioFormat::Mp3 => Some("mp3"),
+            AudioFormat::Aiff => Some("aiff"),
+            AudioFormat::Wav => Some("wav"),
+            AudioFormat::Flac => Some("flac"),
+            AudioFormat::Original => None,
+        }
+    }
+
+    /// Value for yt-dlp's `--audio-format`. yt-dlp can't write AIFF, so it
+    /// extracts WAV which `to_aiff` converts afterwards.
+    fn ytdlp_format(self) -> &'static str {
+        match self {
+            AudioFormat::Mp3 => "mp3",
+            AudioFormat::Aiff | AudioFormat::Wav => "wav",
+            AudioFormat::Flac => "flac",
+            AudioFormat::Original => "best",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct QualityProfile {
+    pub name: String,
+    pub format: AudioFormat,
+    /// Bitrate in kbps, only used by lossy formats
+    #[serde(default)]
+    pub bitrate: Option<u32>,
+}
+
+impl QualityProfile {
+    /// yt-dlp arguments that extract the audio in this profile's format.
+    pub fn ytdlp_args(&self) -> Vec<String> {
+        let mut args = vec![
+            "-x".to_string(),
+            "--audio-format".to_string(),
+            self.format.ytdlp_format().to_string(),
+        ];
+        if let (AudioFormat::Mp3, Some(bitrate)) = (self.format, self.bitrate) {
+            args.push("--audio-quality".to_string());
+            args.push(format!("{}k", bitrate));
+        }
+        // yt-dlp refuses to embed cover art in WAV
+        if !matches!(self.format, AudioFormat::Wav | AudioFormat::Aiff) {
+            args.push("--embed-thumbnail".to_string());
+        }
+        args
+    }
+}
+
+pub fn builtin_profiles() -> Vec<QualityProfile> {
+    let profile = |name: &str, format, bitrate| QualityProfile {
+        name: name.to_string(),
+        format,
+        bitrate,
+    };
+    vec![
+        profile(DEFAULT_PROFILE, AudioFormat::Mp3, Some(320)),
+        profile("AIFF", AudioFormat::Aiff, None),
+        profile("WAV", AudioFormat::Wav, None),
+        profile("FLAC", AudioFormat::Flac, None),
+        profile("Keep original", AudioFormat::Original, None),
+    ]
+}
+
+/// Checks that profile names are set and unique, and that `default` names one of them.
+pub fn validate(profiles: &[QualityProfile], default: &str) -> Result<(), String> {
+    for (i, profile) in profiles.iter().enumerate() {
+        if profile.name.trim().is_empty() {
+            return Err("Quality profile names cannot be empty".into());
+        }
+        if profiles[..i].iter().any(|p| p.name == profile.name) {
+            return Err(format!(
+                "A quality profile named {} already exists",
+                profile.name
+            ));
+        }
+    }
+    if !profiles.iter().any(|p| p.name == default) {
+        return Err(format!("Quality profile {} not found", default));
+    }
+    Ok(())
+}
+
+/// Converts the WAV yt-dlp extracted to AIFF, carrying its tags over as ID3.
+pub async fn to_aiff(app: &AppHandle, wav: &Path, aiff: &Path) -> anyhow::Result<()> {
+    let ffmpeg = bundler::ffmpeg_path(app)?;
+
+    let mut cmd = Command::new(ffmpeg);
+    cmd.args(["-y", "-v", "error", "-i"])
+        .arg(wav)
+        .args([
+            "-c:a",
+            "pcm_s16be",
+            "-write_id3v2",
+            "1",
+            "-id3v2_version",
+            "3",
+        ])
+        .arg(aiff);
+
+    #[cfg(windows)]
+    cmd.creation_flags(bundler::CREATE_NO_WINDOW);
+
+    let output = cmd.output().await?;
+    if !output.status.success() {
+        let _ = std::fs::remove_file(aiff);
+        return Err(anyhow::anyhow!(
+            "ffmpeg failed to convert to AIFF: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+
+    std::fs::remove_file(wav)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_profiles() {
+        let profiles = builtin_profiles();
+        assert!(validate(&profiles, DEFAULT_PROFILE).is_ok());
+        assert!(validate(&profiles, "OGG").is_err());
+
+        let mut duplicated = profiles.clone();
+        duplicated.push(profiles[1].clone());
+        assert!(validate(&duplicated, DEFAULT_PROFILE).is_err());
+
+        assert_eq!(
+            profiles[0].ytdlp_args(),
+            [
+                "-x",
+                "--audio-format",
+                "mp3",
+                "--audio-quality",
+                "320k",
+                "--embed-thumbnail"
+            ]
+        );
+        // AIFF is extracted as WAV, which can't hold cover art
+        assert_eq!(profiles[1].ytdlp_args(), ["-x", "--audio-format", "wav"]);
+        assert_eq!(profiles[1].format.extension(), Some("aiff"));
+        assert_eq!(
+            profiles[4].ytdlp_args(),
+            ["-x", "--audio-format", "best", "--embed-thumbnail"]
+        );
+        assert_eq!(profiles[4].format.extension(), None);
+    }
+}
@@ -1,8 +1,88 @@
 use crate::db::entities::Song;
+use crate::protocol::percent_encode;
 use quick_xml::events::{BytesDecl, BytesEnd, BytesStart, Event};
 use std::fs::File;
 use std::path::Path;
 
+/// Colour labels supported by Rekordbox, with the hex code used in the XML.
+pub const COLOURS: [(&str, &str); 8] = [
+    ("Pink", "0xFF007F"),
+    ("Red", "0xFF0000"),
+    ("Orange", "0xFFA500"),
+    ("Yellow", "0xFFFF00"),
+    ("Green", "0x00FF00"),
+    ("Aqua", "0x25FDE9"),
+    ("Blue", "0x0000FF"),
+    ("Purple", "0x660099"),
+];
+
+fn find_colour(name: &str) -> Option<&'static (&'static str, &'static str)> {
+    COLOURS
+        .iter()
+        .find(|(label, _)| label.eq_ignore_ascii_case(name))
+}
+
+pub fn colour_code(name: &str) -> Option<&'static str> {
+    find_colour(name).map(|(_, code)| *code)
+}
+
+/// The label as spelled in `COLOURS`, so "red" is stored and filtered as "Red".
+pub fn colour_label(name: &str) -> Option<&'static str> {
+    find_colour(name).map(|(label, _)| *label)
+}
+
+/// File type shown in Rekordbox's Kind column, from the file's extension.
+fn kind(filename: &str) -> String {
+    let ext = Path::new(filename)
+        .extension()
+        .map(|e| e.to_string_lossy().to_uppercase())
+        .unwrap_or_default();
+    match ext.as_str() {
+        "AAC" | "M4A" => "M4A File".to_string(),
+        "AIF" => "AIFF File".to_string(),
+        _ => format!("{} File", ext),
+    }
+}
+
+/// Formats a unix timestamp as the `YYYY-MM-DD` date Rekordbox expects.
+pub fn format_date(timestamp: i64) -> String {
+    // Civil-from-days conversion, see https://howardhinnant.github.io/date_algorithms.html
+    let days = timestamp.div_euclid(86_400);
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+/// Location of `path` as Rekordbox reads it, a file URI with each segment
+/// percent-encoded so spaces, `#`, `%` and non-ASCII names resolve.
+fn file_uri(path: &Path) -> String {
+    let path = path.to_string_lossy().replace('\\', "/");
+    let segments: Vec<String> = path
+        .split('/')
+        .map(|segment| {
+            // Windows drive letters, e.g. "C:", stay as they are
+            if segment.len() == 2 && segment.ends_with(':') {
+                segment.to_string()
+            } else {
+                percent_encode(segment)
+            }
+        })
+        .collect();
+    let path = segments.join("/");
+    if path.starts_with('/') {
+        format!("file://localhost{}", path)
+    } else {
+        format!("file://localhost/{}", path)
+    }
+}
+
 pub async fn export_xml(songs: Vec<Song>, library_path: &str) -> anyhow::Result<()> {
     let xml_path = Path::new(library_path).join("rekordbox.xml");
 
@@ -32,16 +112,37 @@ pub async fn export_xml(songs: Vec<Song>, library_path: &str) -> anyhow::Result<
         let mut track = BytesStart::new("TRACK");
         track.push_attribute(("TrackID", song.id.as_str()));
         track.push_attribute(("Name", song.title.as_str()));
-        track.push_attribute(("Artist", song.artist.as_str()));
+        track.push_attribute(("Artist", song.full_artist().as_str()));
+        if let Some(ref version) = song.version {
+            track.push_attribute(("Mix", version.as_str()));
+        }
+        if let Some(ref remixer) = song.remixer {
+            track.push_attribute(("Remixer", remixer.as_str()));
+        }
         if let Some(ref album) = song.album {
             track.push_attribute(("Album", album.as_str()));
         }
-        track.push_attribute(("Kind", "MP3 File"));
+        if let Some(number) = song.track_number {
+            track.push_attribute(("TrackNumber", number.to_string().as_str()));
+        }
+        track.push_attribute(("Kind", kind(&song.filename).as_str()));
+
+        // Rekordbox stores stars as 0-255 in steps of 51
+        let rating = (song.rating.min(5) as u32 * 51).to_string();
+        track.push_attribute(("Rating", rating.as_str()));
+        if let Some(code) = song.colour.as_deref().and_then(colour_code) {
+            track.push_attribute(("Colour", code));
+        }
+        if let Some(ref comments) = song.comments {
+            track.push_attribute(("Comments", comments.as_str()));
+        }
+        track.push_attribute(("PlayCount", song.play_count.to_string().as_str()));
+        if let Some(created_at) = song.created_at {
+            track.push_attribute(("DateAdded", format_date(created_at).as_str()));
+        }
 
-        // Format Location as file URI
         let song_path = Path::new(library_path).join("Songs").join(&song.filename);
-        let location = format!("file://localhost{}", song_path.to_string_lossy());
-        track.push_attribute(("Location", location.as_str()));
+        track.push_attribute(("Location", file_uri(&song_path).as_str()));
 
         writer.write_event(Event::Empty(track))?;
     }
@@ -56,3 +157,24 @@ pub async fn export_xml(songs: Vec<Song>, library_path: &str) -> anyhow::Result<
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_file_uri() {
+        assert_eq!(
+            file_uri(Path::new("/Music/Songs/Artist/#1 Hit 100%.mp3")),
+            "file://localhost/Music/Songs/Artist/%231%20Hit%20100%25.mp3"
+        );
+        assert_eq!(
+            file_uri(Path::new("/Music/Songs/Beyoncé.mp3")),
+            "file://localhost/Music/Songs/Beyonc%C3%A9.mp3"
+        );
+        assert_eq!(
+            file_uri(Path::new("C:\\Music\\Songs\\a b.mp3")),
+            "file://localhost/C:/Music/Songs/a%20b.mp3"
+        );
+    }
+}
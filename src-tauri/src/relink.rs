@@ -0,0 +1,421 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Emitter};
+use tokio::process::Command;
+
+use crate::bundler;
+use crate::db::entities::Song;
+use crate::db::Database;
+use crate::filename::{self, TemplateFields};
+use crate::hashing;
+use crate::integrity::is_audio_file;
+
+/// Largest difference in seconds for two durations to count as the same track.
+const DURATION_TOLERANCE: f64 = 2.0;
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MatchMethod {
+    ContentHash,
+    Filename,
+    DurationAndTags,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct RelinkProposal {
+    pub song_id: String,
+    pub source_path: String,
+    pub new_filename: String,
+    pub method: MatchMethod,
+}
+
+#[derive(Clone, Serialize)]
+pub struct RelinkProgressPayload {
+    pub status: String,
+    pub progress: f64,
+}
+
+#[derive(Deserialize)]
+struct ProbeOutput {
+    format: Option<ProbeFormat>,
+}
+
+#[derive(Deserialize)]
+struct ProbeFormat {
+    duration: Option<String>,
+    tags: Option<HashMap<String, String>>,
+}
+
+#[derive(Default)]
+struct ProbedTags {
+    title: Option<String>,
+    artist: Option<String>,
+    duration: Option<f64>,
+}
+
+/// Lazily computed facts about a candidate file, so each is hashed or probed once.
+struct Candidate {
+    path: PathBuf,
+    hash: Option<Option<String>>,
+    tags: Option<ProbedTags>,
+}
+
+fn collect_candidates(dir: &Path, out: &mut Vec<Candidate>) {
+    let entries = match fs::read_dir(dir) {
+        Ok(e) => e,
+        Err(e) => {
+            eprintln!("Failed to read {}: {}", dir.display(), e);
+            return;
+        }
+    };
+
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.is_dir() {
+            collect_candidates(&path, out);
+        } else if is_audio_file(&path) {
+            out.push(Candidate {
+                path,
+                hash: None,
+                tags: None,
+            });
+        }
+    }
+}
+
+async fn probe_tags(ffprobe: &Path, path: &Path) -> ProbedTags {
+    let mut cmd = Command::new(ffprobe);
+    cmd.args(["-v", "error", "-of", "json"])
+        .args(["-show_entries", "format=duration:format_tags=title,artist"])
+        .arg(path);
+
+    #[cfg(windows)]
+    cmd.creation_flags(bundler::CREATE_NO_WINDOW);
+
+    let output = match cmd.output().await {
+        Ok(o) if o.status.success() => o,
+        _ => return ProbedTags::default(),
+    };
+
+    let format = match serde_json::from_slice::<ProbeOutput>(&output.stdout) {
+        Ok(ProbeOutput { format: Some(f) }) => f,
+        _ => return ProbedTags::default(),
+    };
+
+    // Tag keys come back in whatever case the file was written with
+    let tag = |key: &str| {
+        format.tags.as_ref().and_then(|tags| {
+            tags.iter()
+                .find(|(k, _)| k.eq_ignore_ascii_case(key))
+                .map(|(_, v)| v.clone())
+        })
+    };
+
+    ProbedTags {
+        title: tag("title"),
+        artist: tag("artist"),
+        duration: format.duration.as_deref().and_then(|d| d.parse().ok()),
+    }
+}
+
+fn same_text(a: &str, b: Option<&String>) -> bool {
+    b.is_some_and(|b| a.trim().eq_ignore_ascii_case(b.trim()))
+}
+
+fn tags_match(song: &Song, tags: &ProbedTags) -> bool {
+    let durations_match = match (song.duration, tags.duration) {
+        (Some(a), Some(b)) => (a - b).abs() <= DURATION_TOLERANCE,
+        _ => false,
+    };
+    durations_match
+        && same_text(&song.title, tags.title.as_ref())
+        && same_text(&song.artist, tags.artist.as_ref())
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default()
+}
+
+/// Finds the file of `song` among `candidates`, skipping those in `claimed`
+/// that were already matched to another song.
+async fn find_match(
+    ffprobe: Option<&Path>,
+    song: &Song,
+    candidates: &mut [Candidate],
+    claimed: &HashSet<usize>,
+) -> anyhow::Result<Option<(usize, MatchMethod)>> {
+    if let Some(ref stored) = song.content_hash {
+        for (i, candidate) in candidates.iter_mut().enumerate() {
+            if claimed.contains(&i) {
+                continue;
+            }
+            if candidate.hash.is_none() {
+                let hash = hashing::hash_file_async(&candidate.path)
+                    .await
+                    .ok()
+                    .map(|(h, _)| h);
+                candidate.hash = Some(hash);
+            }
+            if candidate.hash.as_ref().and_then(|h| h.as_ref()) == Some(stored) {
+                return Ok(Some((i, MatchMethod::ContentHash)));
+            }
+        }
+    }
+
+    let wanted = file_name(Path::new(&song.filename));
+    let by_name = (0..candidates.len())
+        .find(|i| !claimed.contains(i) && file_name(&candidates[*i].path) == wanted);
+    if let Some(i) = by_name {
+        return Ok(Some((i, MatchMethod::Filename)));
+    }
+
+    if let Some(ffprobe) = ffprobe {
+        for (i, candidate) in candidates.iter_mut().enumerate() {
+            if claimed.contains(&i) {
+                continue;
+            }
+            if candidate.tags.is_none() {
+                candidate.tags = Some(probe_tags(ffprobe, &candidate.path).await);
+            }
+            if candidate.tags.as_ref().is_some_and(|t| tags_match(song, t)) {
+                return Ok(Some((i, MatchMethod::DurationAndTags)));
+            }
+        }
+    }
+
+    Ok(None)
+}
+
+/// Searches `roots` for the files of songs missing from `Songs/` and proposes
+/// where to relink each of them from, named after `template`. Each file is
+/// proposed for one song at most.
+pub async fn find_missing(
+    app: &AppHandle,
+    db: &Database,
+    roots: &[String],
+    template: &str,
+) -> anyhow::Result<Vec<RelinkProposal>> {
+    let songs_dir = Path::new(&db.library_path).join("Songs");
+    let missing: Vec<Song> = db
+        .get_songs()
+        .await?
+        .into_iter()
+        .filter(|s| !songs_dir.join(&s.filename).exists())
+        .collect();
+
+    if missing.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let mut candidates = Vec::new();
+    for root in roots {
+        collect_candidates(Path::new(root), &mut candidates);
+    }
+
+    let ffprobe = bundler::ffprobe_path(app).ok();
+    let total = missing.len() as f64;
+    let mut proposals = Vec::new();
+    let mut claimed = HashSet::new();
+
+    for (i, song) in missing.iter().enumerate() {
+        let _ = app.emit(
+            "library://relink-progress",
+            RelinkProgressPayload {
+                status: format!("Searching for {}", song.title),
+                progress: i as f64 / total * 100.0,
+            },
+        );
+
+        let found = find_match(ffprobe.as_deref(), song, &mut candidates, &claimed).await?;
+        if let Some((index, method)) = found {
+            claimed.insert(index);
+            let candidate = &candidates[index];
+            let ext = candidate
+                .path
+                .extension()
+                .map(|e| e.to_string_lossy().to_lowercase())
+                .unwrap_or_default();
+            let rendered = filename::render(template, &TemplateFields::from_song(song));
+            proposals.push(RelinkProposal {
+                song_id: song.id.clone(),
+                source_path: candidate.path.to_string_lossy().to_string(),
+                new_filename: format!("{}.{}", rendered, ext),
+                method,
+            });
+        }
+    }
+
+    let _ = app.emit(
+        "library://relink-progress",
+        RelinkProgressPayload {
+            status: "Done".to_string(),
+            progress: 100.0,
+        },
+    );
+
+    Ok(proposals)
+}
+
+/// Copies each proposed file into `Songs/` under its proposed name, made
+/// unique, and points its song at the copy. The song's stored hash is only
+/// replaced when the file was matched by it, so a weaker match can't overwrite
+/// the reference a later verify compares against.
+pub async fn apply(db: &Database, proposals: &[RelinkProposal]) -> anyhow::Result<usize> {
+    let songs_dir = Path::new(&db.library_path).join("Songs");
+
+    let mut applied = 0;
+    for proposal in proposals {
+        let song = db
+            .get_song_by_id(&proposal.song_id)
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("Song {} not found", proposal.song_id))?;
+
+        let wanted = Path::new(&proposal.new_filename);
+        let ext = wanted
+            .extension()
+            .map(|e| e.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let rendered = proposal
+            .new_filename
+            .strip_suffix(&format!(".{}", ext))
+            .unwrap_or(&proposal.new_filename);
+        let filename = filename::unique(db, rendered, &song.id, &ext).await?;
+
+        let dest = songs_dir.join(&filename);
+        if let Some(parent) = dest.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::copy(&proposal.source_path, &dest)?;
+
+        db.update_song_filename(&song.id, &filename).await?;
+        if song.content_hash.is_none() || matches!(proposal.method, MatchMethod::ContentHash) {
+            let (hash, mtime) = hashing::hash_file_async(&dest).await?;
+            db.update_song_hash(&song.id, &hash, mtime).await?;
+        }
+        applied += 1;
+    }
+    Ok(applied)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_find_match() {
+        let dir = tempfile::tempdir().unwrap();
+        let renamed = dir.path().join("renamed.mp3");
+        let moved = dir.path().join("Old").join("moved.mp3");
+        fs::create_dir_all(moved.parent().unwrap()).unwrap();
+        fs::write(&renamed, b"first").unwrap();
+        fs::write(&moved, b"second").unwrap();
+        fs::write(dir.path().join("notes.txt"), b"first").unwrap();
+
+        let mut candidates = Vec::new();
+        collect_candidates(dir.path(), &mut candidates);
+        assert_eq!(candidates.len(), 2);
+
+        // A stored hash finds the file whatever it's called now
+        let hashed = Song {
+            id: "1".to_string(),
+            filename: "first.mp3".to_string(),
+            content_hash: Some(hashing::hash_file(&renamed).unwrap()),
+            ..Default::default()
+        };
+        let (index, method) = find_match(None, &hashed, &mut candidates, &HashSet::new())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(candidates[index].path, renamed);
+        assert!(matches!(method, MatchMethod::ContentHash));
+
+        // Without one, a file with the same name in another folder does
+        let unhashed = Song {
+            id: "2".to_string(),
+            filename: "moved.mp3".to_string(),
+            ..Default::default()
+        };
+        let (index, method) = find_match(None, &unhashed, &mut candidates, &HashSet::new())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(candidates[index].path, moved);
+        assert!(matches!(method, MatchMethod::Filename));
+
+        // Once claimed by another song, a file isn't proposed again
+        let claimed = HashSet::from([index]);
+        assert!(find_match(None, &unhashed, &mut candidates, &claimed)
+            .await
+            .unwrap()
+            .is_none());
+
+        let unknown = Song {
+            id: "3".to_string(),
+            filename: "unknown.mp3".to_string(),
+            content_hash: Some("0".repeat(64)),
+            ..Default::default()
+        };
+        assert!(find_match(None, &unknown, &mut candidates, &HashSet::new())
+            .await
+            .unwrap()
+            .is_none());
+    }
+
+    #[tokio::test]
+    async fn test_apply() {
+        let (library, elsewhere) = (tempfile::tempdir().unwrap(), tempfile::tempdir().unwrap());
+        let songs = library.path().join("Songs");
+        fs::create_dir_all(songs.join("Artist")).unwrap();
+        fs::write(songs.join("Artist").join("Song.mp3"), b"taken").unwrap();
+        let (first, second) = (
+            elsewhere.path().join("a.mp3"),
+            elsewhere.path().join("b.mp3"),
+        );
+        fs::write(&first, b"audio").unwrap();
+        fs::write(&second, b"other").unwrap();
+
+        let db = Database::open(&library.path().to_string_lossy())
+            .await
+            .unwrap();
+        let stale = "0".repeat(64);
+        for (id, content_hash) in [("1", None), ("2", Some(stale.clone()))] {
+            let song = Song {
+                id: id.to_string(),
+                title: "Song".to_string(),
+                artist: "Artist".to_string(),
+                filename: format!("lost{}.mp3", id),
+                content_hash,
+                ..Default::default()
+            };
+            db.add_song(&song).await.unwrap();
+        }
+
+        let proposal = |id: &str, source: &Path| RelinkProposal {
+            song_id: id.to_string(),
+            source_path: source.to_string_lossy().to_string(),
+            new_filename: "Artist/Song.mp3".to_string(),
+            method: MatchMethod::Filename,
+        };
+        let proposals = [proposal("1", &first), proposal("2", &second)];
+        assert_eq!(apply(&db, &proposals).await.unwrap(), 2);
+
+        // The name was taken, so the copies are told apart, inside the template's folder
+        let song = db.get_song_by_id("1").await.unwrap().unwrap();
+        assert_eq!(song.filename, "Artist/Song [1].mp3");
+        assert_eq!(fs::read(songs.join(&song.filename)).unwrap(), b"audio");
+        assert_eq!(
+            fs::read(songs.join("Artist").join("Song.mp3")).unwrap(),
+            b"taken"
+        );
+        assert!(first.exists());
+        // A song without a hash gets one, a matched-by-name file doesn't replace it
+        assert_eq!(song.content_hash, Some(hashing::hash_file(&first).unwrap()));
+        let song = db.get_song_by_id("2").await.unwrap().unwrap();
+        assert_eq!(song.filename, "Artist/Song [2].mp3");
+        assert_eq!(song.content_hash, Some(stale));
+    }
+}
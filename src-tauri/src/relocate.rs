@@ -0,0 +1,297 @@
+use serde::Serialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Emitter};
+
+use crate::db::Database;
+use crate::hashing;
+
+/// Folders inside the library that travel with it, relative to the library root.
+const LIBRARY_DIRS: [&str; 3] = ["Songs", ".trash", "Backups"];
+/// Exported files that are copied as-is and regenerated once the move is done.
+const EXPORT_FILES: [&str; 1] = ["rekordbox.xml"];
+const DB_FILE: &str = "songs.db";
+
+#[derive(Clone, Serialize)]
+pub struct RelocateProgressPayload {
+    pub status: String,
+    pub progress: f64,
+}
+
+fn emit_progress(app: &AppHandle, status: &str, progress: f64) {
+    let _ = app.emit(
+        "library://relocate-progress",
+        RelocateProgressPayload {
+            status: status.to_string(),
+            progress,
+        },
+    );
+}
+
+fn collect_files(dir: &Path, files: &mut Vec<PathBuf>) -> std::io::Result<()> {
+    if !dir.exists() {
+        return Ok(());
+    }
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            collect_files(&path, files)?;
+        } else {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+/// Lists every file that belongs to the library, relative to its root.
+fn library_files(root: &Path) -> std::io::Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    for dir in LIBRARY_DIRS {
+        collect_files(&root.join(dir), &mut files)?;
+    }
+    for file in EXPORT_FILES {
+        let path = root.join(file);
+        if path.exists() {
+            files.push(path);
+        }
+    }
+
+    Ok(files
+        .into_iter()
+        .filter_map(|p| p.strip_prefix(root).ok().map(Path::to_path_buf))
+        .collect())
+}
+
+fn validate_target(from: &Path, to: &Path) -> anyhow::Result<()> {
+    let from = fs::canonicalize(from)?;
+    fs::create_dir_all(to)?;
+    let to = fs::canonicalize(to)?;
+
+    if from == to {
+        return Err(anyhow::anyhow!("The library is already in this folder"));
+    }
+    if to.starts_with(&from) {
+        return Err(anyhow::anyhow!(
+            "The new location cannot be inside the current library"
+        ));
+    }
+    // Rollback deletes these wholesale, so none of them may exist beforehand
+    let occupied = LIBRARY_DIRS
+        .iter()
+        .chain(&EXPORT_FILES)
+        .chain(&[DB_FILE])
+        .any(|name| to.join(name).exists());
+    if occupied {
+        return Err(anyhow::anyhow!(
+            "The new location already contains a library"
+        ));
+    }
+    Ok(())
+}
+
+fn remove_library_files(root: &Path) {
+    for dir in LIBRARY_DIRS {
+        let path = root.join(dir);
+        if path.exists() {
+            if let Err(e) = fs::remove_dir_all(&path) {
+                eprintln!("Failed to remove {}: {}", path.display(), e);
+            }
+        }
+    }
+
+    for file in EXPORT_FILES
+        .iter()
+        .chain(&[DB_FILE, "songs.db-wal", "songs.db-shm"])
+    {
+        let path = root.join(file);
+        if path.exists() {
+            if let Err(e) = fs::remove_file(&path) {
+                eprintln!("Failed to remove {}: {}", path.display(), e);
+            }
+        }
+    }
+}
+
+/// Reports the status of the move and its progress out of 100.
+type Progress<'a> = &'a (dyn Fn(&str, f64) + Sync);
+
+/// Copies one file, `fs::copy` outside of tests.
+type CopyFile = fn(&Path, &Path) -> std::io::Result<u64>;
+
+async fn copy_library(
+    db: &Database,
+    to: &Path,
+    copy: CopyFile,
+    progress: Progress<'_>,
+) -> anyhow::Result<Database> {
+    let from = Path::new(&db.library_path);
+    let files = library_files(from)?;
+    let total = files.len().max(1) as f64;
+
+    for (i, rel) in files.iter().enumerate() {
+        let (src, dest) = (from.join(rel), to.join(rel));
+        if let Some(parent) = dest.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        copy(&src, &dest)?;
+
+        // Compared with the source rather than the stored hash, a song that
+        // already drifted is reported by verification and still moves
+        let intact =
+            hashing::hash_file_async(&src).await?.0 == hashing::hash_file_async(&dest).await?.0;
+        if !intact {
+            return Err(anyhow::anyhow!(
+                "Verification failed for {}",
+                rel.to_string_lossy()
+            ));
+        }
+
+        progress(
+            &format!("Copying {}", rel.to_string_lossy()),
+            (i + 1) as f64 / total * 90.0,
+        );
+    }
+
+    // VACUUM INTO writes a consistent copy even while the pool is in use
+    progress("Copying database", 92.0);
+    let new_db_path = to.join(DB_FILE);
+    sqlx::query("VACUUM INTO ?1")
+        .bind(new_db_path.to_string_lossy().to_string())
+        .execute(&db.pool)
+        .await?;
+
+    let new_db = Database::open(&to.to_string_lossy()).await?;
+
+    let expected = db.get_songs().await?.len();
+    let actual = new_db.get_songs().await?.len();
+    if expected != actual {
+        new_db.pool.close().await;
+        return Err(anyhow::anyhow!(
+            "Verification failed: copied database has {} songs, expected {}",
+            actual,
+            expected
+        ));
+    }
+
+    Ok(new_db)
+}
+
+/// Copies the library to `to` and returns a `Database` opened at the new location.
+/// Nothing is left behind in `to` if any step fails.
+pub async fn copy_to(app: &AppHandle, db: &Database, to: &Path) -> anyhow::Result<Database> {
+    copy_with_progress(
+        db,
+        to,
+        |from, to| fs::copy(from, to),
+        &|status, progress| emit_progress(app, status, progress),
+    )
+    .await
+}
+
+async fn copy_with_progress(
+    db: &Database,
+    to: &Path,
+    copy: CopyFile,
+    progress: Progress<'_>,
+) -> anyhow::Result<Database> {
+    validate_target(Path::new(&db.library_path), to)?;
+
+    match copy_library(db, to, copy, progress).await {
+        Ok(new_db) => {
+            progress("Exporting", 96.0);
+            new_db.trigger_rekordbox_export().await;
+            progress("Done", 100.0);
+            Ok(new_db)
+        }
+        Err(e) => {
+            progress("Rolling back", 0.0);
+            remove_library_files(to);
+            Err(e)
+        }
+    }
+}
+
+/// Closes `db` and deletes its library files, used to drop either the old location
+/// after a move or a new copy that could not be switched to.
+pub async fn discard(db: &Database) {
+    db.pool.close().await;
+    remove_library_files(Path::new(&db.library_path));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::entities::Song;
+
+    /// A library with one hashed song, whose stored hash is wrong if `stale_hash`.
+    async fn library_with_song(dir: &Path, stale_hash: bool) -> Database {
+        let path = dir.join("Songs").join("song.mp3");
+        fs::create_dir_all(dir.join("Songs")).unwrap();
+        fs::write(&path, b"audio").unwrap();
+        let content_hash = if stale_hash {
+            "0".repeat(64)
+        } else {
+            hashing::hash_file(&path).unwrap()
+        };
+
+        let db = Database::open(&dir.to_string_lossy()).await.unwrap();
+        let song = Song {
+            id: "1".to_string(),
+            title: "Song".to_string(),
+            artist: "Artist".to_string(),
+            filename: "song.mp3".to_string(),
+            content_hash: Some(content_hash),
+            ..Default::default()
+        };
+        db.add_song(&song).await.unwrap();
+        db
+    }
+
+    #[tokio::test]
+    async fn test_copy_library() {
+        let (from, to) = (tempfile::tempdir().unwrap(), tempfile::tempdir().unwrap());
+        // A file that no longer matches its stored hash moves like any other
+        let db = library_with_song(from.path(), true).await;
+
+        let new_db = copy_with_progress(&db, to.path(), |from, to| fs::copy(from, to), &|_, _| {})
+            .await
+            .unwrap();
+        assert_eq!(new_db.get_songs().await.unwrap().len(), 1);
+        assert!(to.path().join("Songs").join("song.mp3").exists());
+        assert!(to.path().join("rekordbox.xml").exists());
+
+        // Copying again onto a library that's already there is refused
+        assert!(
+            copy_with_progress(&db, to.path(), |from, to| fs::copy(from, to), &|_, _| {})
+                .await
+                .is_err()
+        );
+    }
+
+    /// Copies like `fs::copy`, but songs arrive with different content.
+    fn corrupting_copy(from: &Path, to: &Path) -> std::io::Result<u64> {
+        let copied = fs::copy(from, to)?;
+        if to.extension().is_some_and(|ext| ext == "mp3") {
+            fs::write(to, b"noise")?;
+        }
+        Ok(copied)
+    }
+
+    #[tokio::test]
+    async fn test_copy_rolls_back_on_mismatch() {
+        let (from, to) = (tempfile::tempdir().unwrap(), tempfile::tempdir().unwrap());
+        let db = library_with_song(from.path(), false).await;
+
+        let error = match copy_with_progress(&db, to.path(), corrupting_copy, &|_, _| {}).await {
+            Ok(_) => panic!("copied a library whose file failed verification"),
+            Err(e) => e.to_string(),
+        };
+        assert!(error.contains("song.mp3"));
+        assert!(!to.path().join("Songs").exists());
+        assert!(!to.path().join(DB_FILE).exists());
+
+        // The original is left as it was
+        assert!(from.path().join("Songs").join("song.mp3").exists());
+        assert_eq!(db.get_songs().await.unwrap().len(), 1);
+    }
+}
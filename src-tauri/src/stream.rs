@@ -0,0 +1,176 @@
+use std::io::SeekFrom;
+use std::path::Path;
+use tauri::http::{header, Request, Response, StatusCode};
+use tauri::AppHandle;
+use tokio::io::{AsyncReadExt, AsyncSeekExt};
+
+use crate::protocol::{self, not_found, percent_decode};
+
+/// Name of the URI scheme the webview streams audio from, as
+/// `cue-audio://localhost/<song_id>`.
+pub const PROTOCOL: &str = "cue-audio";
+
+/// Most bytes returned for an open-ended range, so seeking never reads a whole file.
+const MAX_CHUNK: u64 = 1024 * 1024;
+
+fn content_type(path: &Path) -> &'static str {
+    let ext = path
+        .extension()
+        .map(|e| e.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    match ext.as_str() {
+        "mp3" => "audio/mpeg",
+        "m4a" | "aac" => "audio/mp4",
+        "flac" => "audio/flac",
+        "wav" => "audio/wav",
+        "aif" | "aiff" => "audio/aiff",
+        "ogg" | "opus" => "audio/ogg",
+        _ => "application/octet-stream",
+    }
+}
+
+/// Parses a single `bytes=` range against a file of `len` bytes into an
+/// inclusive `(start, end)`. Returns `Err(())` when the range can't be satisfied.
+fn parse_range(value: &str, len: u64) -> Result<(u64, u64), ()> {
+    let spec = value.trim().strip_prefix("bytes=").ok_or(())?;
+    // Multipart ranges aren't supported, serve the first one
+    let spec = spec.split(',').next().ok_or(())?.trim();
+    let (start, end) = spec.split_once('-').ok_or(())?;
+
+    let (start, end) = match (start.trim(), end.trim()) {
+        ("", suffix) => {
+            let suffix: u64 = suffix.parse().map_err(|_| ())?;
+            if suffix == 0 {
+                return Err(());
+            }
+            (len.saturating_sub(suffix), len - 1)
+        }
+        (start, "") => {
+            let start: u64 = start.parse().map_err(|_| ())?;
+            (start, (start + MAX_CHUNK - 1).min(len.saturating_sub(1)))
+        }
+        (start, end) => {
+            let start: u64 = start.parse().map_err(|_| ())?;
+            let end: u64 = end.parse().map_err(|_| ())?;
+            (start, end.min(len.saturating_sub(1)))
+        }
+    };
+
+    if len == 0 || start > end || start >= len {
+        return Err(());
+    }
+    Ok((start, end))
+}
+
+async fn read_range(path: &Path, start: u64, end: u64) -> std::io::Result<Vec<u8>> {
+    let mut file = tokio::fs::File::open(path).await?;
+    file.seek(SeekFrom::Start(start)).await?;
+    let mut buf = vec![0; (end - start + 1) as usize];
+    file.read_exact(&mut buf).await?;
+    Ok(buf)
+}
+
+/// Streams the file of the song whose id is the request path. Only files
+/// inside the library's `Songs/` folder are ever served.
+pub async fn handle_request(app: &AppHandle, request: &Request<Vec<u8>>) -> Response<Vec<u8>> {
+    let song_id = percent_decode(request.uri().path().trim_start_matches('/'));
+
+    let db = match protocol::database(app) {
+        Some(db) => db,
+        None => return not_found(),
+    };
+    let song = match db.get_song_by_id(&song_id).await {
+        Ok(Some(song)) if song.deleted_at.is_none() => song,
+        _ => return not_found(),
+    };
+
+    // Canonicalising catches filenames that would escape the folder, e.g. via `..`
+    let songs_dir = Path::new(&db.library_path).join("Songs");
+    let path = match (
+        songs_dir.join(&song.filename).canonicalize(),
+        songs_dir.canonicalize(),
+    ) {
+        (Ok(path), Ok(dir)) if path.starts_with(&dir) && path.is_file() => path,
+        _ => return not_found(),
+    };
+
+    let len = match tokio::fs::metadata(&path).await {
+        Ok(m) => m.len(),
+        Err(_) => return not_found(),
+    };
+    let mime = content_type(&path);
+
+    let range = request
+        .headers()
+        .get(header::RANGE)
+        .and_then(|v| v.to_str().ok());
+
+    let (status, start, end) = match range {
+        Some(value) => match parse_range(value, len) {
+            Ok((start, end)) => (StatusCode::PARTIAL_CONTENT, start, end),
+            Err(()) => {
+                return Response::builder()
+                    .status(StatusCode::RANGE_NOT_SATISFIABLE)
+                    .header(header::CONTENT_RANGE, format!("bytes */{}", len))
+                    .body(Vec::new())
+                    .unwrap_or_default();
+            }
+        },
+        None if len == 0 => (StatusCode::OK, 0, 0),
+        None => (StatusCode::OK, 0, len - 1),
+    };
+
+    let body = if len == 0 {
+        Vec::new()
+    } else {
+        match read_range(&path, start, end).await {
+            Ok(body) => body,
+            Err(e) => {
+                eprintln!("Failed to read {}: {}", path.display(), e);
+                return protocol::response(
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    Vec::new(),
+                    "text/plain",
+                );
+            }
+        }
+    };
+
+    let mut builder = Response::builder()
+        .status(status)
+        .header(header::CONTENT_TYPE, mime)
+        .header(header::ACCEPT_RANGES, "bytes")
+        .header(header::CONTENT_LENGTH, body.len())
+        .header(header::ACCESS_CONTROL_ALLOW_ORIGIN, "*");
+    if status == StatusCode::PARTIAL_CONTENT {
+        builder = builder.header(
+            header::CONTENT_RANGE,
+            format!("bytes {}-{}/{}", start, end, len),
+        );
+    }
+    builder.body(body).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_range() {
+        assert_eq!(parse_range("bytes=0-99", 1000), Ok((0, 99)));
+        assert_eq!(parse_range("bytes=900-2000", 1000), Ok((900, 999)));
+        assert_eq!(parse_range("bytes=500-", 1000), Ok((500, 999)));
+        assert_eq!(parse_range("bytes=-100", 1000), Ok((900, 999)));
+        assert_eq!(parse_range("bytes=-5000", 1000), Ok((0, 999)));
+        assert_eq!(
+            parse_range("bytes=0-", 10 * MAX_CHUNK),
+            Ok((0, MAX_CHUNK - 1))
+        );
+        assert_eq!(parse_range("bytes=0-1, 5-6", 1000), Ok((0, 1)));
+        assert_eq!(parse_range("bytes=1000-", 1000), Err(()));
+        assert_eq!(parse_range("bytes=50-10", 1000), Err(()));
+        assert_eq!(parse_range("bytes=-0", 1000), Err(()));
+        assert_eq!(parse_range("items=0-1", 1000), Err(()));
+        assert_eq!(parse_range("bytes=0-1", 0), Err(()));
+    }
+}
@@ -0,0 +1,66 @@
+use std::path::Path;
+use tauri::AppHandle;
+use tokio::process::Command;
+
+use crate::bundler;
+use crate::db::entities::Song;
+
+/// Rewrites the tags of `path` from the song, including the remixer and
+/// version. MP3 and AIFF get ID3 frames (TPE4, TIT3), FLAC and Ogg get Vorbis
+/// comments. Audio and artwork are copied untouched.
+pub async fn write_tags(app: &AppHandle, path: &Path, song: &Song) -> anyhow::Result<()> {
+    let ffmpeg = bundler::ffmpeg_path(app)?;
+    let ext = path
+        .extension()
+        .map(|e| e.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    let tmp = path.with_extension(format!("tagging.{}", ext));
+
+    let (muxer_args, version_key, remixer_key): (&[&str], _, _) = match ext.as_str() {
+        "mp3" => (&["-id3v2_version", "3"], "TIT3", "TPE4"),
+        "aif" | "aiff" => (
+            &["-write_id3v2", "1", "-id3v2_version", "3"],
+            "TIT3",
+            "TPE4",
+        ),
+        _ => (&[], "VERSION", "REMIXER"),
+    };
+
+    // An empty value removes the frame, so cleared fields don't linger in the file
+    let tags = [
+        ("title", song.title.clone()),
+        ("artist", song.full_artist()),
+        ("album", song.album.clone().unwrap_or_default()),
+        (
+            "track",
+            song.track_number.map(|n| n.to_string()).unwrap_or_default(),
+        ),
+        (version_key, song.version.clone().unwrap_or_default()),
+        (remixer_key, song.remixer.clone().unwrap_or_default()),
+    ];
+
+    let mut cmd = Command::new(ffmpeg);
+    cmd.args(["-y", "-v", "error", "-i"])
+        .arg(path)
+        .args(["-map", "0", "-c", "copy"])
+        .args(muxer_args);
+    for (key, value) in &tags {
+        cmd.arg("-metadata").arg(format!("{}={}", key, value));
+    }
+    cmd.arg(&tmp);
+
+    #[cfg(windows)]
+    cmd.creation_flags(bundler::CREATE_NO_WINDOW);
+
+    let output = cmd.output().await?;
+    if !output.status.success() {
+        let _ = std::fs::remove_file(&tmp);
+        return Err(anyhow::anyhow!(
+            "ffmpeg failed to write tags: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+
+    std::fs::rename(&tmp, path)?;
+    Ok(())
+}
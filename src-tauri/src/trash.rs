@@ -0,0 +1,287 @@
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::artwork;
+use crate::db::entities::{AuditEntry, Song};
+use crate::db::Database;
+
+const TRASH_DIR: &str = ".trash";
+
+fn song_path(library_path: &str, song: &Song) -> PathBuf {
+    Path::new(library_path).join("Songs").join(&song.filename)
+}
+
+fn trashed_path(library_path: &str, song: &Song) -> PathBuf {
+    Path::new(library_path).join(TRASH_DIR).join(&song.filename)
+}
+
+/// Whether the song's file is still in the library or the trash.
+pub fn has_file(library_path: &str, song: &Song) -> bool {
+    song_path(library_path, song).exists() || trashed_path(library_path, song).exists()
+}
+
+fn move_file(from: &Path, to: &Path) -> std::io::Result<()> {
+    if let Some(parent) = to.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    // rename fails across filesystems, fall back to copy + delete
+    if fs::rename(from, to).is_err() {
+        fs::copy(from, to)?;
+        fs::remove_file(from)?;
+    }
+    Ok(())
+}
+
+/// Moves the song's file into `<library>/.trash` and marks its row as deleted.
+pub async fn move_to_trash(db: &Database, id: &str) -> anyhow::Result<()> {
+    let song = db
+        .get_song_by_id(id)
+        .await?
+        .ok_or_else(|| anyhow::anyhow!("Song not found"))?;
+
+    let from = song_path(&db.library_path, &song);
+    if from.exists() {
+        move_file(&from, &trashed_path(&db.library_path, &song))?;
+    } else {
+        eprintln!("File {} not found, trashing entry only", from.display());
+    }
+
+    db.trash_song(id).await?;
+    Ok(())
+}
+
+/// Marks songs as deleted without moving their file, for entries that share
+/// it with another song that keeps it.
+pub async fn trash_entries(db: &Database, ids: &[String]) -> anyhow::Result<()> {
+    for id in ids {
+        db.trash_song(id).await?;
+    }
+    Ok(())
+}
+
+/// Moves a trashed song's file back into `Songs/` and clears its deleted mark.
+pub async fn restore(db: &Database, id: &str) -> anyhow::Result<()> {
+    let song = db
+        .get_song_by_id(id)
+        .await?
+        .ok_or_else(|| anyhow::anyhow!("Song not found"))?;
+
+    if song.deleted_at.is_none() {
+        return Err(anyhow::anyhow!("Song is not in the trash"));
+    }
+
+    let from = trashed_path(&db.library_path, &song);
+    let to = song_path(&db.library_path, &song);
+    if to.exists() {
+        return Err(anyhow::anyhow!(
+            "A file named {} already exists in the library",
+            song.filename
+        ));
+    }
+    if from.exists() {
+        move_file(&from, &to)?;
+    } else {
+        eprintln!("File {} not found, restoring entry only", from.display());
+    }
+
+    db.restore_song(id).await?;
+    Ok(())
+}
+
+/// Moves the song's file to wherever its row says it should be after `entry`
+/// was undone: back under its old name, in or out of the trash, or deleted
+/// when undoing an add dropped the row.
+pub async fn reconcile(db: &Database, entry: &AuditEntry) -> anyhow::Result<()> {
+    let after: Option<Song> = entry
+        .after
+        .as_deref()
+        .map(serde_json::from_str)
+        .transpose()?;
+    let song = match db.get_song_by_id(&entry.song_id).await? {
+        Some(song) => song,
+        None => {
+            if let Some(after) = after {
+                for path in [
+                    song_path(&db.library_path, &after),
+                    trashed_path(&db.library_path, &after),
+                ] {
+                    if path.exists() {
+                        fs::remove_file(&path)?;
+                    }
+                }
+                artwork::remove(&db.library_path, &after.id);
+            }
+            return Ok(());
+        }
+    };
+
+    // A rename or relink left the file under the name it was given
+    if let Some(after) = after.filter(|a| a.filename != song.filename) {
+        let moves = [
+            (
+                song_path(&db.library_path, &after),
+                song_path(&db.library_path, &song),
+            ),
+            (
+                trashed_path(&db.library_path, &after),
+                trashed_path(&db.library_path, &song),
+            ),
+        ];
+        for (from, to) in moves {
+            if from.exists() && !to.exists() {
+                move_file(&from, &to)?;
+            }
+        }
+    }
+
+    let in_library = song_path(&db.library_path, &song);
+    let in_trash = trashed_path(&db.library_path, &song);
+    if song.deleted_at.is_some() && in_library.exists() && !in_trash.exists() {
+        move_file(&in_library, &in_trash)?;
+    } else if song.deleted_at.is_none() && in_trash.exists() && !in_library.exists() {
+        move_file(&in_trash, &in_library)?;
+    }
+    Ok(())
+}
+
+async fn purge(db: &Database, songs: Vec<Song>) -> anyhow::Result<usize> {
+    // Entries trashed by trash_entries share their file with a song that stays
+    let ids: HashSet<&str> = songs.iter().map(|s| s.id.as_str()).collect();
+    let mut kept = HashSet::new();
+    for song in db
+        .get_songs()
+        .await?
+        .into_iter()
+        .chain(db.get_trashed_songs().await?)
+    {
+        if !ids.contains(song.id.as_str()) {
+            kept.insert(song.filename);
+        }
+    }
+
+    for song in &songs {
+        artwork::remove(&db.library_path, &song.id);
+        if kept.contains(&song.filename) {
+            continue;
+        }
+        let path = trashed_path(&db.library_path, song);
+        if let Err(e) = fs::remove_file(&path) {
+            eprintln!("Failed to delete file {}: {}", path.display(), e);
+        }
+    }
+
+    let ids: Vec<String> = songs.into_iter().map(|s| s.id).collect();
+    db.remove_songs(&ids).await?;
+    Ok(ids.len())
+}
+
+/// Permanently deletes every trashed song, returning how many were removed.
+pub async fn empty(db: &Database) -> anyhow::Result<usize> {
+    let songs = db.get_trashed_songs().await?;
+    purge(db, songs).await
+}
+
+/// Permanently deletes songs that have been in the trash for at least `retention_days`.
+pub async fn purge_expired(db: &Database, retention_days: u32) -> anyhow::Result<usize> {
+    let songs = db.get_expired_trash(retention_days).await?;
+    if songs.is_empty() {
+        return Ok(0);
+    }
+    purge(db, songs).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_trash_and_restore() {
+        let dir = tempfile::tempdir().unwrap();
+        let songs = dir.path().join("Songs");
+        fs::create_dir_all(&songs).unwrap();
+        fs::write(songs.join("song.mp3"), b"audio").unwrap();
+
+        let db = Database::open(&dir.path().to_string_lossy()).await.unwrap();
+        let song = Song {
+            id: "1".to_string(),
+            title: "Song".to_string(),
+            artist: "Artist".to_string(),
+            filename: "song.mp3".to_string(),
+            ..Default::default()
+        };
+        db.add_song(&song).await.unwrap();
+
+        move_to_trash(&db, "1").await.unwrap();
+        assert!(!songs.join("song.mp3").exists());
+        assert!(dir.path().join(TRASH_DIR).join("song.mp3").exists());
+        assert!(db.get_songs().await.unwrap().is_empty());
+        assert_eq!(db.get_trashed_songs().await.unwrap().len(), 1);
+
+        // A new file took the name, restoring would overwrite it
+        fs::write(songs.join("song.mp3"), b"other").unwrap();
+        assert!(restore(&db, "1").await.is_err());
+        fs::remove_file(songs.join("song.mp3")).unwrap();
+
+        restore(&db, "1").await.unwrap();
+        assert_eq!(fs::read(songs.join("song.mp3")).unwrap(), b"audio");
+        assert!(!dir.path().join(TRASH_DIR).join("song.mp3").exists());
+        assert_eq!(db.get_songs().await.unwrap().len(), 1);
+        assert!(restore(&db, "1").await.is_err());
+
+        // A second entry for the same file goes to the trash without it
+        db.add_song(&Song {
+            id: "2".to_string(),
+            title: "Song".to_string(),
+            artist: "Artist".to_string(),
+            filename: "song.mp3".to_string(),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+        trash_entries(&db, &["2".to_string()]).await.unwrap();
+        assert!(songs.join("song.mp3").exists());
+        assert_eq!(empty(&db).await.unwrap(), 1);
+        assert!(songs.join("song.mp3").exists());
+
+        move_to_trash(&db, "1").await.unwrap();
+        assert_eq!(empty(&db).await.unwrap(), 1);
+        assert!(!dir.path().join(TRASH_DIR).join("song.mp3").exists());
+        assert!(db.get_song_by_id("1").await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_reconcile_after_undo() {
+        let dir = tempfile::tempdir().unwrap();
+        let songs = dir.path().join("Songs");
+        fs::create_dir_all(&songs).unwrap();
+        fs::write(songs.join("old.mp3"), b"audio").unwrap();
+
+        let db = Database::open(&dir.path().to_string_lossy()).await.unwrap();
+        let song = Song {
+            id: "1".to_string(),
+            title: "Song".to_string(),
+            artist: "Artist".to_string(),
+            filename: "old.mp3".to_string(),
+            ..Default::default()
+        };
+        db.add_song(&song).await.unwrap();
+
+        // Renamed on disk and in the row, as filename::rename_all does
+        fs::rename(songs.join("old.mp3"), songs.join("new.mp3")).unwrap();
+        db.update_song_filename("1", "new.mp3").await.unwrap();
+        for entry in db.undo_last_change().await.unwrap() {
+            reconcile(&db, &entry).await.unwrap();
+        }
+        assert!(songs.join("old.mp3").exists());
+        assert!(!songs.join("new.mp3").exists());
+
+        // Undoing the download drops the row, the file goes with it
+        for entry in db.undo_last_change().await.unwrap() {
+            reconcile(&db, &entry).await.unwrap();
+        }
+        assert!(db.get_song_by_id("1").await.unwrap().is_none());
+        assert!(!songs.join("old.mp3").exists());
+    }
+}
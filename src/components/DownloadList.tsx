@@ -9,16 +9,26 @@ interface DownloadListProps {
 	downloads: DownloadJob[];
 	removeDownload: (id: string) => void;
 	onClear: () => void;
+	onRetryFailed: () => void;
+	queuePaused: boolean;
+	onToggleQueuePaused: () => void;
 }
 
 export function DownloadList({
 	downloads,
 	removeDownload,
 	onClear,
+	onRetryFailed,
+	queuePaused,
+	onToggleQueuePaused,
 }: DownloadListProps) {
 	const history = downloads.filter(
-		(d) => d.status === "completed" || d.status === "error",
+		(d) =>
+			d.status.kind === "completed" ||
+			d.status.kind === "failed" ||
+			d.status.kind === "cancelled",
 	);
+	const hasFailed = history.some((d) => d.status.kind === "failed");
 
 	return (
 		<div className="space-y-4 w-full">
@@ -27,8 +37,30 @@ export function DownloadList({
 					Recent Activity
 				</h2>
 				<div className="flex items-center gap-3">
+					<Button
+						variant="ghost"
+						size="sm"
+						onClick={onToggleQueuePaused}
+						className={`h-8 px-2 text-xs transition-colors font-medium ${
+							queuePaused
+								? "text-primary"
+								: "text-muted-foreground/60 hover:text-primary"
+						}`}
+					>
+						{queuePaused ? "Resume queue" : "Pause queue"}
+					</Button>
 					{history.length > 0 && (
 						<>
+							{hasFailed && (
+								<Button
+									variant="ghost"
+									size="sm"
+									onClick={onRetryFailed}
+									className="h-8 px-2 text-xs text-muted-foreground/60 hover:text-primary transition-colors font-medium"
+								>
+									Retry failed
+								</Button>
+							)}
 							<Button
 								variant="ghost"
 								size="sm"
@@ -4,22 +4,35 @@ import { ActiveDownloadItem } from "@/components/queue/ActiveDownloadItem";
 import { QueuedDownloadList } from "@/components/queue/QueuedDownloadList";
 import { Card, CardContent } from "@/components/ui/card";
 import type { DownloadJob } from "@/hooks/useDownload";
+import type { QueueMove } from "@/lib/tauri/core/types";
 
 interface DownloadQueueStatusProps {
 	downloads: DownloadJob[];
 	onRemove: (id: string) => void;
+	onPause: (id: string) => void;
+	onResume: (id: string) => void;
+	onMove: (id: string, direction: QueueMove) => void;
 	onClearQueue: () => void;
 }
 
 export function DownloadQueueStatus({
 	downloads,
 	onRemove,
+	onPause,
+	onResume,
+	onMove,
 	onClearQueue,
 }: DownloadQueueStatusProps) {
 	const activeDownload = downloads.find(
-		(d) => d.status === "pending" || d.status === "downloading",
+		(d) =>
+			d.status.kind === "fetching" ||
+			d.status.kind === "downloading" ||
+			d.status.kind === "post_processing",
 	);
-	const queuedDownloads = downloads.filter((d) => d.status === "queued");
+	// Same order the backend starts them in, sort is stable for equal priorities
+	const queuedDownloads = downloads
+		.filter((d) => d.status.kind === "queued" || d.status.kind === "paused")
+		.sort((a, b) => (b.priority ?? 0) - (a.priority ?? 0));
 
 	if (!activeDownload && queuedDownloads.length === 0) {
 		return null;
@@ -35,7 +48,11 @@ export function DownloadQueueStatus({
 			<Card className="border-primary/20 bg-primary/5 backdrop-blur-xl shadow-2xl overflow-hidden">
 				<CardContent className="p-4">
 					{activeDownload ? (
-						<ActiveDownloadItem download={activeDownload} onRemove={onRemove} />
+						<ActiveDownloadItem
+							download={activeDownload}
+							onRemove={onRemove}
+							onPause={onPause}
+						/>
 					) : (
 						<div className="flex items-center gap-3 text-muted-foreground">
 							<div className="bg-muted p-2 rounded-full">
@@ -48,6 +65,8 @@ export function DownloadQueueStatus({
 					<QueuedDownloadList
 						downloads={queuedDownloads}
 						onRemove={onRemove}
+						onResume={onResume}
+						onMove={onMove}
 						onClearQueue={onClearQueue}
 					/>
 				</CardContent>
@@ -17,10 +17,10 @@ export function StatusIcon({
 	const containerPadding = size === "sm" ? "p-1" : "p-2";
 
 	const getStatusStyles = () => {
-		switch (status) {
+		switch (status.kind) {
 			case "completed":
 				return "bg-green-500/20 text-green-500";
-			case "error":
+			case "failed":
 				return "bg-destructive/20 text-destructive";
 			default:
 				return "bg-primary/10 text-primary";
@@ -28,10 +28,10 @@ export function StatusIcon({
 	};
 
 	const Icon = () => {
-		switch (status) {
+		switch (status.kind) {
 			case "completed":
 				return <CheckCircle2 className={iconSize} />;
-			case "error":
+			case "failed":
 				return <AlertCircle className={iconSize} />;
 			default:
 				return <Music className={iconSize} />;
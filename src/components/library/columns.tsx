@@ -1,14 +1,89 @@
 import type { ColumnDef } from "@tanstack/react-table";
-import { AlertCircle, Plus, Trash2 } from "lucide-react";
-import { useState } from "react";
+import { AlertCircle, Music, Pause, Play, Plus, Trash2 } from "lucide-react";
+import { useEffect, useState } from "react";
 
 import { TagBadge } from "@/components/library/TagBadge";
 import { TagInput } from "@/components/library/TagInput";
 import { Button } from "@/components/ui/button";
 import type { Song } from "@/lib/tauri/core/types";
+import { artworkUrl, audioUrl } from "@/lib/utils";
 
 export type { Song };
 
+const Artwork = ({ songId }: { songId: string }) => {
+	const [failed, setFailed] = useState(false);
+
+	// Songs without a cover get a 404, show a placeholder instead
+	if (failed) {
+		return (
+			<div className="w-8 h-8 shrink-0 rounded bg-muted flex items-center justify-center">
+				<Music className="w-4 h-4 text-muted-foreground" />
+			</div>
+		);
+	}
+
+	return (
+		<img
+			src={artworkUrl(songId, 64)}
+			alt=""
+			loading="lazy"
+			className="w-8 h-8 shrink-0 rounded object-cover"
+			onError={() => setFailed(true)}
+		/>
+	);
+};
+
+// One element for the whole table, so starting a preview stops the previous one
+const preview = new Audio();
+
+const PreviewButton = ({
+	songId,
+	disabled,
+}: {
+	songId: string;
+	disabled: boolean;
+}) => {
+	const src = audioUrl(songId);
+	const [playing, setPlaying] = useState(
+		() => preview.src === src && !preview.paused,
+	);
+
+	useEffect(() => {
+		const update = () => setPlaying(preview.src === src && !preview.paused);
+		const events = ["play", "pause", "ended", "emptied"];
+		for (const event of events) preview.addEventListener(event, update);
+		return () => {
+			for (const event of events) preview.removeEventListener(event, update);
+		};
+	}, [src]);
+
+	const toggle = () => {
+		if (playing) {
+			preview.pause();
+			return;
+		}
+		if (preview.src !== src) preview.src = src;
+		preview.play().catch((e) => console.error("Failed to play preview:", e));
+	};
+
+	return (
+		<Button
+			variant="ghost"
+			size="icon"
+			disabled={disabled}
+			onClick={toggle}
+			aria-label={playing ? "Pause preview" : "Play preview"}
+			className="text-muted-foreground hover:text-foreground transition-colors"
+		>
+			{playing ? (
+				<Pause className="w-4 h-4" />
+			) : (
+				<Play className="w-4 h-4" />
+			)}
+		</Button>
+	);
+};
+
 const TagsCell = ({
 	song,
 	onUpdate,
@@ -80,7 +155,11 @@ export const createColumns = (
 						isMissing ? "File missing locally. Use Sync to re-download." : ""
 					}
 				>
-					{isMissing && <AlertCircle className="w-4 h-4 text-destructive" />}
+					{isMissing ? (
+						<AlertCircle className="w-4 h-4 text-destructive" />
+					) : (
+						<Artwork songId={row.original.id} />
+					)}
 					<span className="truncate">{row.getValue("title")}</span>
 				</div>
 			);
@@ -120,9 +199,13 @@ export const createColumns = (
 	{
 		id: "actions",
 		header: () => <div className="text-right">Actions</div>,
-		size: 80,
+		size: 100,
 		cell: ({ row }) => (
-			<div className="text-right">
+			<div className="flex justify-end">
+				<PreviewButton
+					songId={row.original.id}
+					disabled={missingIds.has(row.original.id)}
+				/>
 				<Button
 					variant="ghost"
 					size="icon"
@@ -1,5 +1,5 @@
 import { AnimatePresence, motion } from "framer-motion";
-import { ChevronDown, ChevronUp, X } from "lucide-react";
+import { ChevronDown, ChevronUp, Pause, X } from "lucide-react";
 import { useEffect, useRef, useState } from "react";
 import { SongMetadata } from "@/components/download/SongMetadata";
 import { StatusIcon } from "@/components/download/StatusIcon";
@@ -10,11 +10,13 @@ import type { DownloadJob } from "@/hooks/useDownload";
 interface ActiveDownloadItemProps {
 	download: DownloadJob;
 	onRemove: (id: string) => void;
+	onPause: (id: string) => void;
 }
 
 export function ActiveDownloadItem({
 	download,
 	onRemove,
+	onPause,
 }: ActiveDownloadItemProps) {
 	const [showLogs, setShowLogs] = useState(false);
 	const logEndRef = useRef<HTMLDivElement>(null);
@@ -54,6 +56,16 @@ export function ActiveDownloadItem({
 							)}
 						</Button>
 					)}
+					{download.status.kind !== "post_processing" && (
+						<Button
+							variant="ghost"
+							size="icon"
+							className="h-8 w-8 rounded-full hover:bg-primary/10 hover:text-primary"
+							onClick={() => onPause(download.id)}
+						>
+							<Pause className="w-4 h-4" />
+						</Button>
+					)}
 					<Button
 						variant="ghost"
 						size="icon"
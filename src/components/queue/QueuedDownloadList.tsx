@@ -1,19 +1,32 @@
 import { AnimatePresence, motion } from "framer-motion";
-import { ChevronDown, ChevronUp, X } from "lucide-react";
+import {
+	ArrowDown,
+	ArrowUp,
+	ArrowUpToLine,
+	ChevronDown,
+	ChevronUp,
+	Play,
+	X,
+} from "lucide-react";
 import { useState } from "react";
 import { SongMetadata } from "@/components/download/SongMetadata";
 import { Button } from "@/components/ui/button";
 import type { DownloadJob } from "@/hooks/useDownload";
+import type { QueueMove } from "@/lib/tauri/core/types";
 
 interface QueuedDownloadListProps {
 	downloads: DownloadJob[];
 	onRemove: (id: string) => void;
+	onResume: (id: string) => void;
+	onMove: (id: string, direction: QueueMove) => void;
 	onClearQueue: () => void;
 }
 
 export function QueuedDownloadList({
 	downloads,
 	onRemove,
+	onResume,
+	onMove,
 	onClearQueue,
 }: QueuedDownloadListProps) {
 	const [isExpanded, setIsExpanded] = useState(false);
@@ -58,12 +71,52 @@ export function QueuedDownloadList({
 						exit={{ height: 0, opacity: 0 }}
 						className="overflow-hidden mt-2 space-y-2"
 					>
-						{downloads.map((job) => (
+						{downloads.map((job, index) => (
 							<div
 								key={job.id}
 								className="flex items-center gap-3 p-2 rounded-lg bg-background/50 border border-muted-foreground/5"
 							>
 								<SongMetadata metadata={job.metadata} size="sm" />
+								{index > 0 && (
+									<>
+										<Button
+											variant="ghost"
+											size="icon"
+											className="h-6 w-6 rounded-full hover:bg-primary/10 hover:text-primary"
+											onClick={() => onMove(job.id, "top")}
+										>
+											<ArrowUpToLine className="w-3 h-3" />
+										</Button>
+										<Button
+											variant="ghost"
+											size="icon"
+											className="h-6 w-6 rounded-full hover:bg-primary/10 hover:text-primary"
+											onClick={() => onMove(job.id, "up")}
+										>
+											<ArrowUp className="w-3 h-3" />
+										</Button>
+									</>
+								)}
+								{index < downloads.length - 1 && (
+									<Button
+										variant="ghost"
+										size="icon"
+										className="h-6 w-6 rounded-full hover:bg-primary/10 hover:text-primary"
+										onClick={() => onMove(job.id, "down")}
+									>
+										<ArrowDown className="w-3 h-3" />
+									</Button>
+								)}
+								{job.status.kind === "paused" && (
+									<Button
+										variant="ghost"
+										size="icon"
+										className="h-6 w-6 rounded-full hover:bg-primary/10 hover:text-primary"
+										onClick={() => onResume(job.id)}
+									>
+										<Play className="w-3 h-3" />
+									</Button>
+								)}
 								<Button
 									variant="ghost"
 									size="icon"
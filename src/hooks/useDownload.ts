@@ -1,6 +1,11 @@
 import { useCallback, useEffect, useState } from "react";
 import { toast } from "sonner";
-import type { DownloadJob, MetadataPayload } from "@/lib/tauri/core/types";
+import type {
+	DownloadJob,
+	MetadataPayload,
+	QueueMove,
+	QueueOptions,
+} from "@/lib/tauri/core/types";
 import { useTauri } from "@/lib/tauri/TauriProvider";
 
 export type { DownloadJob };
@@ -8,10 +13,19 @@ export type { DownloadJob };
 export function useDownload() {
 	const tauri = useTauri();
 	const [downloads, setDownloads] = useState<DownloadJob[]>([]);
+	const [queuePaused, setQueuePausedState] = useState(false);
 
 	useEffect(() => {
 		// Initial fetch
 		tauri.getDownloads().then(setDownloads).catch(console.error);
+		tauri.isQueuePaused().then(setQueuePausedState).catch(console.error);
+
+		const unlistenQueuePaused = tauri.listen(
+			"download://queue-paused",
+			(event) => {
+				setQueuePausedState(event.payload);
+			},
+		);
 
 		const unlistenList = tauri.listen("download://list-updated", (event) => {
 			setDownloads(event.payload);
@@ -29,7 +43,7 @@ export function useDownload() {
 						return {
 							...d,
 							progress: payload.progress === -1 ? d.progress : payload.progress,
-							status: payload.status as DownloadJob["status"],
+							status: payload.status,
 							detailed_status: payload.detailed_status || d.detailed_status,
 							logs: newLogs,
 						};
@@ -50,13 +64,14 @@ export function useDownload() {
 			unlistenList.then((f) => f());
 			unlistenProgress.then((f) => f());
 			unlistenError.then((f) => f());
+			unlistenQueuePaused.then((f) => f());
 		};
 	}, [tauri]);
 
 	const startDownload = useCallback(
-		async (url: string, metadata: MetadataPayload) => {
+		async (url: string, metadata: MetadataPayload, options?: QueueOptions) => {
 			try {
-				await tauri.addToQueue(url, metadata.id, metadata);
+				await tauri.addToQueue(url, metadata.id, metadata, options);
 			} catch (err) {
 				toast.error(`Failed to add to queue: ${err}`);
 			}
@@ -67,7 +82,12 @@ export function useDownload() {
 	const removeDownload = useCallback(
 		(id: string) => {
 			const job = downloads.find((d) => d.id === id);
-			if (job && (job.status === "downloading" || job.status === "pending")) {
+			if (
+				job &&
+				(job.status.kind === "fetching" ||
+					job.status.kind === "downloading" ||
+					job.status.kind === "post_processing")
+			) {
 				tauri.cancelDownload(id).catch(console.error);
 			}
 			tauri.removeDownload(id).catch(console.error);
@@ -83,11 +103,51 @@ export function useDownload() {
 		tauri.clearQueue().catch(console.error);
 	}, [tauri]);
 
+	const pauseDownload = useCallback(
+		(id: string) => {
+			tauri
+				.pauseDownload(id)
+				.catch((err) => toast.error(`Failed to pause download: ${err}`));
+		},
+		[tauri],
+	);
+
+	const resumeDownload = useCallback(
+		(id: string) => {
+			tauri.resumeDownload(id).catch(console.error);
+		},
+		[tauri],
+	);
+
+	const moveDownload = useCallback(
+		(id: string, direction: QueueMove) => {
+			tauri.moveDownload(id, direction).catch(console.error);
+		},
+		[tauri],
+	);
+
+	const setQueuePaused = useCallback(
+		(paused: boolean) => {
+			tauri.setQueuePaused(paused).catch(console.error);
+		},
+		[tauri],
+	);
+
+	const retryFailed = useCallback(() => {
+		tauri.retryFailedDownloads().catch(console.error);
+	}, [tauri]);
+
 	return {
 		downloads,
 		startDownload,
 		removeDownload,
 		clearHistory,
 		clearQueue,
+		retryFailed,
+		pauseDownload,
+		resumeDownload,
+		moveDownload,
+		queuePaused,
+		setQueuePaused,
 	};
 }
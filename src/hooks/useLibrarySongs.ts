@@ -1,37 +1,89 @@
-import { useCallback, useEffect, useState } from "react";
+import { useCallback, useEffect, useRef, useState } from "react";
 import { toast } from "sonner";
 import type { Song } from "@/lib/tauri/core/types";
 import { useTauri } from "@/lib/tauri/TauriProvider";
 
-const ACTIVE_STATUSES = new Set(["queued", "pending", "downloading"]);
+const ACTIVE_STATUSES = new Set([
+	"queued",
+	"fetching",
+	"downloading",
+	"post_processing",
+	"paused",
+]);
+
+// Songs fetched per request as the collection is scrolled
+const PAGE_SIZE = 200;
 
 export function useLibrarySongs() {
 	const tauri = useTauri();
 	const [songs, setSongs] = useState<Song[]>([]);
+	const [total, setTotal] = useState(0);
 	const [loading, setLoading] = useState(true);
+	const [loadingMore, setLoadingMore] = useState(false);
 	const [missingIds, setMissingIds] = useState<Set<string>>(new Set());
+	// Read by the callbacks without making them change on every page
+	const loadedRef = useRef(0);
+	const loadingMoreRef = useRef(false);
 
-	const fetchSongs = useCallback(async () => {
+	const fetchMissing = useCallback(async () => {
 		try {
-			const [data, missing, downloads] = await Promise.all([
-				tauri.getSongs(),
+			const [missing, downloads] = await Promise.all([
 				tauri.checkMissingSongs(),
 				tauri.getDownloads(),
 			]);
 
-			setSongs(data);
-
 			const activeIds = new Set(
-				downloads.filter((d) => ACTIVE_STATUSES.has(d.status)).map((d) => d.id),
+				downloads.filter((d) => ACTIVE_STATUSES.has(d.status.kind)).map((d) => d.id),
 			);
 
 			setMissingIds(new Set(missing.filter((id) => !activeIds.has(id))));
+		} catch (error) {
+			console.error("Failed to check missing songs:", error);
+		}
+	}, [tauri]);
+
+	// Reloads every page loaded so far, so a refresh keeps the scroll position
+	const fetchSongs = useCallback(async () => {
+		try {
+			const pages = Math.max(1, Math.ceil(loadedRef.current / PAGE_SIZE));
+			const results = await Promise.all(
+				Array.from({ length: pages }, (_, i) =>
+					tauri.getSongsPage({ offset: i * PAGE_SIZE, limit: PAGE_SIZE }),
+				),
+			);
+			const data = results.flatMap((page) => page.songs);
+
+			loadedRef.current = data.length;
+			setSongs(data);
+			setTotal(results[0]?.total ?? 0);
 		} catch (error) {
 			console.error("Failed to fetch songs:", error);
 		} finally {
 			setLoading(false);
 		}
-	}, [tauri]);
+		await fetchMissing();
+	}, [fetchMissing, tauri]);
+
+	const loadMore = useCallback(async () => {
+		if (loadingMoreRef.current || loadedRef.current >= total) return;
+		loadingMoreRef.current = true;
+		setLoadingMore(true);
+
+		try {
+			const page = await tauri.getSongsPage({
+				offset: loadedRef.current,
+				limit: PAGE_SIZE,
+			});
+			loadedRef.current += page.songs.length;
+			setSongs((prev) => [...prev, ...page.songs]);
+			setTotal(page.total);
+		} catch (error) {
+			console.error("Failed to fetch songs:", error);
+		} finally {
+			loadingMoreRef.current = false;
+			setLoadingMore(false);
+		}
+	}, [tauri, total]);
 
 	useEffect(() => {
 		fetchSongs();
@@ -40,21 +92,24 @@ export function useLibrarySongs() {
 			fetchSongs();
 		});
 
+		// Finished downloads also update the library, the queue only changes what counts as missing
 		const unlistenDownloads = tauri.listen("download://list-updated", () => {
-			fetchSongs();
+			fetchMissing();
 		});
 
 		return () => {
 			unlistenLibrary.then((f) => f());
 			unlistenDownloads.then((f) => f());
 		};
-	}, [fetchSongs, tauri]);
+	}, [fetchMissing, fetchSongs, tauri]);
 
 	const handleDelete = useCallback(
 		async (id: string) => {
 			try {
 				await tauri.removeSong(id);
+				loadedRef.current = Math.max(0, loadedRef.current - 1);
 				setSongs((prev) => prev.filter((song) => song.id !== id));
+				setTotal((prev) => Math.max(0, prev - 1));
 			} catch (error) {
 				console.error("Failed to delete song:", error);
 			}
@@ -78,7 +133,10 @@ export function useLibrarySongs() {
 	const handleSyncAll = useCallback(async () => {
 		if (missingIds.size === 0) return;
 
-		const toSync = songs.filter((s) => missingIds.has(s.id));
+		// Missing songs may be further down than what has been loaded
+		const toSync = (
+			await Promise.all([...missingIds].map((id) => tauri.getSongById(id)))
+		).filter((s): s is Song => s !== null);
 		const withUrl = toSync.filter((s) => s.source_url);
 		const withoutUrl = toSync.filter((s) => !s.source_url);
 
@@ -99,12 +157,16 @@ export function useLibrarySongs() {
 			console.error("Failed to sync songs:", error);
 			toast.error("Failed to sync some songs");
 		}
-	}, [missingIds, songs, tauri]);
+	}, [missingIds, tauri]);
 
 	return {
 		songs,
+		total,
 		loading,
+		loadingMore,
+		hasMore: songs.length < total,
 		missingIds,
+		loadMore,
 		handleDelete,
 		handleUpdateTags,
 		handleSyncAll,
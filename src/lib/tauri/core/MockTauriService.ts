@@ -3,7 +3,11 @@ import type {
 	Config,
 	DownloadJob,
 	MetadataPayload,
+	QueueMove,
+	QueueOptions,
 	Song,
+	SongPage,
+	SongQuery,
 	TauriEventMap,
 	TauriService,
 } from "./types";
@@ -63,8 +67,9 @@ export class MockTauriService implements TauriService {
 		url: string,
 		id: string,
 		metadata: MetadataPayload,
+		options?: QueueOptions,
 	): Promise<void> {
-		console.log("[Mock] addToQueue:", { url, id, metadata });
+		console.log("[Mock] addToQueue:", { url, id, metadata, options });
 
 		// Simulate download progress
 		let progress = 0;
@@ -73,7 +78,7 @@ export class MockTauriService implements TauriService {
 			this.emit("download-progress", {
 				id,
 				progress,
-				status: progress >= 100 ? "completed" : "downloading",
+				status: { kind: progress >= 100 ? "completed" : "downloading" },
 				detailed_status: `Downloading... ${progress}%`,
 			});
 
@@ -89,7 +94,7 @@ export class MockTauriService implements TauriService {
 				id: "mock-dl-1",
 				title: "Mock Active Download",
 				progress: 45,
-				status: "downloading",
+				status: { kind: "downloading" },
 				detailed_status: "Downloading audio...",
 				url: "https://example.com/1",
 				metadata: {
@@ -119,6 +124,36 @@ export class MockTauriService implements TauriService {
 		console.log("[Mock] clearQueue");
 	}
 
+	async retryFailedDownloads(): Promise<number> {
+		console.log("[Mock] retryFailedDownloads");
+		return 0;
+	}
+
+	async pauseDownload(id: string): Promise<void> {
+		console.log("[Mock] pauseDownload:", id);
+	}
+
+	async resumeDownload(id: string): Promise<void> {
+		console.log("[Mock] resumeDownload:", id);
+	}
+
+	async moveDownload(id: string, direction: QueueMove): Promise<void> {
+		console.log("[Mock] moveDownload:", { id, direction });
+	}
+
+	async reorderQueue(ids: string[]): Promise<void> {
+		console.log("[Mock] reorderQueue:", ids);
+	}
+
+	async setQueuePaused(paused: boolean): Promise<void> {
+		console.log("[Mock] setQueuePaused:", paused);
+		this.emit("download://queue-paused", paused);
+	}
+
+	async isQueuePaused(): Promise<boolean> {
+		return false;
+	}
+
 	async checkHealth(): Promise<boolean> {
 		return true;
 	}
@@ -144,6 +179,15 @@ export class MockTauriService implements TauriService {
 		];
 	}
 
+	async getSongsPage(query: SongQuery): Promise<SongPage> {
+		const songs = await this.getSongs();
+		const offset = query.offset ?? 0;
+		return {
+			songs: songs.slice(offset, offset + (query.limit ?? 100)),
+			total: songs.length,
+		};
+	}
+
 	async removeSong(id: string): Promise<void> {
 		console.error("[Mock] removeSong:", id);
 	}
@@ -12,7 +12,11 @@ import type {
 	Config,
 	DownloadJob,
 	MetadataPayload,
+	QueueMove,
+	QueueOptions,
 	Song,
+	SongPage,
+	SongQuery,
 	TauriEventMap,
 	TauriService,
 } from "./types";
@@ -50,8 +54,9 @@ export class RealTauriService implements TauriService {
 		url: string,
 		id: string,
 		metadata: MetadataPayload,
+		options?: QueueOptions,
 	): Promise<void> {
-		return await invoke("add_to_queue", { url, id, metadata });
+		return await invoke("add_to_queue", { url, id, metadata, options });
 	}
 
 	async getDownloads(): Promise<DownloadJob[]> {
@@ -70,6 +75,34 @@ export class RealTauriService implements TauriService {
 		return await invoke("clear_queue");
 	}
 
+	async retryFailedDownloads(): Promise<number> {
+		return await invoke<number>("retry_failed_downloads");
+	}
+
+	async pauseDownload(id: string): Promise<void> {
+		return await invoke("pause_download", { id });
+	}
+
+	async resumeDownload(id: string): Promise<void> {
+		return await invoke("resume_download", { id });
+	}
+
+	async moveDownload(id: string, direction: QueueMove): Promise<void> {
+		return await invoke("move_download", { id, direction });
+	}
+
+	async reorderQueue(ids: string[]): Promise<void> {
+		return await invoke("reorder_queue", { ids });
+	}
+
+	async setQueuePaused(paused: boolean): Promise<void> {
+		return await invoke("set_queue_paused", { paused });
+	}
+
+	async isQueuePaused(): Promise<boolean> {
+		return await invoke<boolean>("is_queue_paused");
+	}
+
 	async checkHealth(): Promise<boolean> {
 		return await invoke<boolean>("check_health");
 	}
@@ -78,6 +111,10 @@ export class RealTauriService implements TauriService {
 		return await invoke<Song[]>("get_songs");
 	}
 
+	async getSongsPage(query: SongQuery): Promise<SongPage> {
+		return await invoke<SongPage>("get_songs_page", { query });
+	}
+
 	async removeSong(id: string): Promise<void> {
 		return await invoke("remove_song", { id });
 	}
@@ -7,9 +7,32 @@ import type { Update } from "@tauri-apps/plugin-updater";
 
 export type { Update };
 
+export interface LibraryProfile {
+	name: string;
+	library_path: string;
+}
+
 export interface Config {
 	library_path: string;
+	profiles?: LibraryProfile[];
+	active_profile?: string;
 	auto_update: boolean;
+	trash_retention_days?: number;
+	backup_interval_hours?: number;
+	backup_keep?: number;
+	filename_template?: string;
+	max_concurrent_downloads?: number;
+	domain_limits?: Record<string, number>;
+	quality_profiles?: QualityProfile[];
+	default_quality_profile?: string;
+}
+
+export type AudioFormat = "mp3" | "aiff" | "wav" | "flac" | "original";
+
+export interface QualityProfile {
+	name: string;
+	format: AudioFormat;
+	bitrate?: number | null;
 }
 
 export interface MetadataPayload {
@@ -20,23 +43,66 @@ export interface MetadataPayload {
 	album: string | null;
 	thumbnail: string | null;
 	duration: number | null;
+	version?: string | null;
+	remixer?: string | null;
+	featured_artists?: string | null;
+	clip_start?: number | null;
+	clip_end?: number | null;
+	chapters?: Chapter[];
+	track_number?: number | null;
+}
+
+export interface Chapter {
+	title: string;
+	start: number;
+	end: number | null;
+}
+
+export interface QueueOptions {
+	profile?: string;
+	start?: number;
+	end?: number;
+	split_chapters?: boolean;
+}
+
+export type JobStatus =
+	| { kind: "queued" }
+	| { kind: "fetching" }
+	| { kind: "downloading" }
+	| { kind: "post_processing" }
+	| { kind: "completed" }
+	| { kind: "failed"; reason: string }
+	| { kind: "cancelled" }
+	| { kind: "paused" };
+
+export interface StatusChange {
+	status: JobStatus;
+	at: number;
 }
 
 export interface DownloadJob {
 	id: string;
 	title: string;
 	progress: number;
-	status: "queued" | "pending" | "downloading" | "completed" | "error";
+	status: JobStatus;
 	detailed_status?: string;
 	url: string;
 	metadata: MetadataPayload;
 	logs: string[];
+	attempts?: number;
+	transitions?: StatusChange[];
+	retry_at?: number | null;
+	priority?: number;
+	profile?: string | null;
+	output?: string | null;
 }
 
+export type QueueMove = "top" | "up" | "down";
+
 export interface DownloadProgressPayload {
 	id: string;
 	progress: number;
-	status: "pending" | "downloading" | "completed" | "error";
+	status: JobStatus;
 	detailed_status?: string;
 	log?: string;
 }
@@ -55,6 +121,109 @@ export interface Song {
 	filename: string;
 	source_url?: string | null;
 	tags?: string | null;
+	rating?: number;
+	colour?: string | null;
+	comments?: string | null;
+	play_count?: number;
+	deleted_at?: number | null;
+	content_hash?: string | null;
+	file_mtime?: number | null;
+	duration?: number | null;
+	created_at?: number | null;
+	updated_at?: number | null;
+	downloaded_at?: number | null;
+	version?: string | null;
+	remixer?: string | null;
+	featured_artists?: string | null;
+	clip_start?: number | null;
+	clip_end?: number | null;
+	track_number?: number | null;
+	quality_profile?: string | null;
+}
+
+export interface AuditEntry {
+	id: number;
+	change_id: number;
+	song_id: string;
+	action: string;
+	actor: string | null;
+	before: string | null;
+	after: string | null;
+	created_at: number;
+	undone: boolean;
+}
+
+export interface BackupInfo {
+	path: string;
+	created_at: number;
+	size: number;
+}
+
+export type IntegrityFinding =
+	| { kind: "missing_file"; song_id: string; filename: string }
+	| { kind: "orphan_file"; filename: string }
+	| { kind: "corrupt_file"; song_id: string; filename: string; error: string }
+	| { kind: "duplicate_filename"; filename: string; song_ids: string[] }
+	| {
+			kind: "hash_changed";
+			song_id: string;
+			filename: string;
+			modified: boolean;
+	  };
+
+export type IntegrityFix =
+	| { action: "redownload"; song_id: string }
+	| { action: "trash_song"; song_id: string }
+	| { action: "import_file"; filename: string }
+	| { action: "trash_entries"; song_ids: string[] }
+	| { action: "accept_hash"; song_id: string };
+
+export interface VerifyResult {
+	song_id: string;
+	status: "ok" | "changed" | "missing" | "hashed";
+}
+
+export type SongSort =
+	| "title"
+	| "artist"
+	| "album"
+	| "rating"
+	| "play_count"
+	| "duration"
+	| "created_at"
+	| "updated_at";
+
+export interface SongFilter {
+	search?: string | null;
+	tag?: string | null;
+	min_rating?: number;
+	colour?: string | null;
+	added_after?: number | null;
+}
+
+export interface SongQuery {
+	sort?: SongSort;
+	direction?: "asc" | "desc";
+	offset?: number;
+	limit?: number;
+	filter?: SongFilter;
+}
+
+export interface SongPage {
+	songs: Song[];
+	total: number;
+}
+
+export interface RelinkProposal {
+	song_id: string;
+	source_path: string;
+	new_filename: string;
+	method: "content_hash" | "filename" | "duration_and_tags";
+}
+
+export interface IntegrityIssue {
+	finding: IntegrityFinding;
+	fix: IntegrityFix;
 }
 
 export interface TauriEventMap {
@@ -62,7 +231,11 @@ export interface TauriEventMap {
 	"download://list-updated": DownloadJob[];
 	"download://progress": DownloadProgressPayload;
 	"download://error": DownloadErrorPayload;
+	"download://queue-paused": boolean;
 	"library://updated": undefined;
+	"library://relocate-progress": { status: string; progress: number };
+	"library://integrity-progress": { status: string; progress: number };
+	"library://relink-progress": { status: string; progress: number };
 	"setup://progress": { status: string; progress: number };
 }
 
@@ -75,13 +248,26 @@ export interface TauriService {
 	readFileContent(path: string): Promise<string>;
 	getSongById(id: string): Promise<Song | null>;
 	cancelDownload(id: string): Promise<void>;
-	addToQueue(url: string, id: string, metadata: MetadataPayload): Promise<void>;
+	addToQueue(
+		url: string,
+		id: string,
+		metadata: MetadataPayload,
+		options?: QueueOptions,
+	): Promise<void>;
 	getDownloads(): Promise<DownloadJob[]>;
 	removeDownload(id: string): Promise<void>;
 	clearHistory(): Promise<void>;
 	clearQueue(): Promise<void>;
+	retryFailedDownloads(): Promise<number>;
+	pauseDownload(id: string): Promise<void>;
+	resumeDownload(id: string): Promise<void>;
+	moveDownload(id: string, direction: QueueMove): Promise<void>;
+	reorderQueue(ids: string[]): Promise<void>;
+	setQueuePaused(paused: boolean): Promise<void>;
+	isQueuePaused(): Promise<boolean>;
 	checkHealth(): Promise<boolean>;
 	getSongs(): Promise<Song[]>;
+	getSongsPage(query: SongQuery): Promise<SongPage>;
 	removeSong(id: string): Promise<void>;
 	factoryReset(): Promise<void>;
 	checkMissingSongs(): Promise<string[]>;
@@ -19,3 +19,18 @@ export function stringToColor(str: string) {
 	// that guarantees good contrast with white text.
 	return `hsl(${h}, 50%, 35%)`;
 }
+
+// Windows and Android webviews serve custom schemes over http://<scheme>.localhost
+function protocolBase(scheme: string) {
+	return /Windows|Android/.test(navigator.userAgent)
+		? `http://${scheme}.localhost`
+		: `${scheme}://localhost`;
+}
+
+export function artworkUrl(songId: string, size: 64 | 256 | 512 = 256) {
+	return `${protocolBase("cue-art")}/${encodeURIComponent(songId)}/${size}`;
+}
+
+export function audioUrl(songId: string) {
+	return `${protocolBase("cue-audio")}/${encodeURIComponent(songId)}`;
+}
@@ -17,7 +17,18 @@ export const Route = createFileRoute("/")({
 function Index() {
 	const tauri = useTauri();
 	const [url, setUrl] = useState("");
-	const { downloads, removeDownload, clearQueue, clearHistory } = useDownload();
+	const {
+		downloads,
+		removeDownload,
+		clearQueue,
+		clearHistory,
+		retryFailed,
+		pauseDownload,
+		resumeDownload,
+		moveDownload,
+		queuePaused,
+		setQueuePaused,
+	} = useDownload();
 	const { queueUrl, loading } = useSmartQueue();
 
 	const handleAddDownload = async () => {
@@ -132,6 +143,9 @@ function Index() {
 					<DownloadQueueStatus
 						downloads={downloads}
 						onRemove={removeDownload}
+						onPause={pauseDownload}
+						onResume={resumeDownload}
+						onMove={moveDownload}
 						onClearQueue={clearQueue}
 					/>
 
@@ -139,6 +153,9 @@ function Index() {
 						downloads={downloads}
 						removeDownload={removeDownload}
 						onClear={clearHistory}
+						onRetryFailed={retryFailed}
+						queuePaused={queuePaused}
+						onToggleQueuePaused={() => setQueuePaused(!queuePaused)}
 					/>
 				</div>
 			</div>
@@ -1,6 +1,6 @@
 import { createFileRoute } from "@tanstack/react-router";
 import { Disc3, Loader2, Music, RefreshCw } from "lucide-react";
-import { useMemo } from "react";
+import { useEffect, useMemo, useRef } from "react";
 
 import { Header } from "@/components/Header";
 import { createColumns } from "@/components/library/columns";
@@ -16,8 +16,12 @@ export const Route = createFileRoute("/library")({
 function Library() {
 	const {
 		songs,
+		total,
 		loading,
+		loadingMore,
+		hasMore,
 		missingIds,
+		loadMore,
 		handleDelete,
 		handleUpdateTags,
 		handleSyncAll,
@@ -28,6 +32,22 @@ function Library() {
 		[handleDelete, handleUpdateTags, missingIds],
 	);
 
+	// Fetches the next page as the end of the table scrolls into view
+	const sentinelRef = useRef<HTMLDivElement>(null);
+	useEffect(() => {
+		const sentinel = sentinelRef.current;
+		if (!sentinel || !hasMore) return;
+
+		const observer = new IntersectionObserver(
+			(entries) => {
+				if (entries.some((e) => e.isIntersecting)) loadMore();
+			},
+			{ rootMargin: "600px" },
+		);
+		observer.observe(sentinel);
+		return () => observer.disconnect();
+	}, [hasMore, loadMore]);
+
 	return (
 		<div className="min-h-screen bg-background flex flex-col items-center p-4 pt-28 pb-28">
 			<div className="w-full max-w-4xl flex flex-col gap-8">
@@ -39,6 +59,11 @@ function Library() {
 							<CardTitle className="flex items-center gap-2 text-2xl font-semibold tracking-tight">
 								<Disc3 className="w-6 h-6 text-primary" />
 								Collection
+								{total > 0 && (
+									<span className="text-sm font-normal text-muted-foreground">
+										{total}
+									</span>
+								)}
 							</CardTitle>
 							{missingIds.size > 0 && (
 								<Button
@@ -67,7 +92,14 @@ function Library() {
 								<p className="text-muted-foreground">Your library is empty.</p>
 							</div>
 						) : (
-							<DataTable columns={columns} data={songs} />
+							<>
+								<DataTable columns={columns} data={songs} />
+								<div ref={sentinelRef} className="flex justify-center py-4">
+									{loadingMore && (
+										<Loader2 className="w-5 h-5 animate-spin text-muted-foreground" />
+									)}
+								</div>
+							</>
 						)}
 					</CardContent>
 				</Card>